@@ -1,4 +1,4 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 use std::process::Command;
@@ -6,8 +6,39 @@ use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 
-#[cfg(target_os = "linux")]
-use std::process::Stdio;
+use crate::hooks::{self, BlockedKind};
+use crate::notifications::{self, Action, Kind, Notification};
+
+/// Where an app was installed from, when that identifies its processes
+/// better than the executable name does.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind", content = "id", rename_all = "lowercase")]
+pub enum AppSource {
+    /// A snap, identified by its instance name.
+    Snap(String),
+    /// An AppImage, identified by the absolute path of the image.
+    AppImage(String),
+}
+
+impl AppSource {
+    /// Parses the `snap:<name>` / `appimage:<path>` form used by the frontend.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.split_once(':')? {
+            ("snap", name) if !name.is_empty() => Some(AppSource::Snap(name.to_string())),
+            ("appimage", path) if path.starts_with('/') => {
+                Some(AppSource::AppImage(path.to_string()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        match self {
+            AppSource::Snap(name) => format!("snap:{}", name),
+            AppSource::AppImage(path) => format!("appimage:{}", path),
+        }
+    }
+}
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct BlockedApp {
@@ -15,6 +46,22 @@ pub struct BlockedApp {
     pub executable: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub icon: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<AppSource>,
+}
+
+impl BlockedApp {
+    /// Parses the `name|||executable|||icon|||source` form the frontend
+    /// sends. The source part is optional.
+    pub fn from_encoded(value: &str) -> Self {
+        let parts: Vec<&str> = value.split("|||").collect();
+        BlockedApp {
+            name: parts.first().unwrap_or(&"Unknown").to_string(),
+            executable: parts.get(1).unwrap_or(&"unknown").to_string(),
+            icon: parts.get(2).map(|s| s.to_string()),
+            source: parts.get(3).and_then(|s| AppSource::parse(s)),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -69,7 +116,7 @@ impl AppBlocker {
                 };
 
                 for app in &apps_to_block {
-                    if let Ok(pids) = find_process_by_name(&app.executable) {
+                    if let Ok(pids) = find_app_processes(app) {
                         if !pids.is_empty() {
                             println!("?? Detected blocked app: {} (PIDs: {:?})", app.name, pids);
 
@@ -79,6 +126,7 @@ impl AppBlocker {
                             }
 
                             show_block_notification(&app.name, app.icon.as_deref());
+                            hooks::spawn_block_attempt(BlockedKind::App, &app.name);
 
                             for pid in pids {
                                 if let Err(e) = kill_process(pid) {
@@ -121,7 +169,15 @@ impl AppBlocker {
     }
 }
 
-fn find_process_by_name(name: &str) -> Result<Vec<u32>, String> {
+fn find_app_processes(app: &BlockedApp) -> Result<Vec<u32>, String> {
+    find_processes(|proc_path| match &app.source {
+        Some(AppSource::Snap(name)) => is_snap_process(proc_path, name),
+        Some(AppSource::AppImage(path)) => is_appimage_process(proc_path, path),
+        None => is_process_match(proc_path, &app.executable),
+    })
+}
+
+fn find_processes<F: Fn(&Path) -> bool>(matches: F) -> Result<Vec<u32>, String> {
     let mut pids = Vec::new();
 
     let proc_dir = Path::new("/proc");
@@ -132,7 +188,7 @@ fn find_process_by_name(name: &str) -> Result<Vec<u32>, String> {
             if let Some(file_name) = path.file_name() {
                 if let Some(file_name_str) = file_name.to_str() {
                     if let Ok(pid) = file_name_str.parse::<u32>() {
-                        if is_process_match(&path, name) {
+                        if matches(&path) {
                             pids.push(pid);
                         }
                     }
@@ -176,6 +232,28 @@ fn is_process_match(proc_path: &Path, target: &str) -> bool {
     false
 }
 
+/// Snapd runs every app of a snap in a `snap.<name>.<app>-<id>.scope`
+/// cgroup, which also covers helpers with unrelated process names.
+fn is_snap_process(proc_path: &Path, snap_name: &str) -> bool {
+    let scope = format!("/snap.{}.", snap_name);
+    fs::read_to_string(proc_path.join("cgroup")).is_ok_and(|cgroup| cgroup.contains(&scope))
+}
+
+/// The AppImage runtime is the image itself, and it exports `APPIMAGE` with
+/// the image path to the app it mounts and starts.
+fn is_appimage_process(proc_path: &Path, image_path: &str) -> bool {
+    if fs::read_link(proc_path.join("exe")).is_ok_and(|exe| exe == Path::new(image_path)) {
+        return true;
+    }
+
+    let expected = format!("APPIMAGE={}", image_path);
+    fs::read(proc_path.join("environ")).is_ok_and(|environ| {
+        environ
+            .split(|b| *b == 0)
+            .any(|var| var == expected.as_bytes())
+    })
+}
+
 fn kill_process(pid: u32) -> Result<(), String> {
     let term_result = Command::new("kill")
         .arg("-15")
@@ -214,145 +292,12 @@ fn process_exists(pid: u32) -> bool {
     Path::new(&format!("/proc/{}", pid)).exists()
 }
 
-pub fn search_installed_apps(query: &str) -> Result<Vec<InstalledApp>, String> {
-    let mut apps = Vec::new();
-    let mut seen_names = HashSet::new();
-
-    let query_lower = query.to_lowercase();
-
-    let home = std::env::var("HOME").unwrap_or_default();
-    let user_apps = format!("{}/.local/share/applications", home);
-    let flatpak_apps = format!("{}/.local/share/flatpak/exports/share/applications", home);
-
-    let search_paths = vec![
-        "/usr/share/applications",
-        "/usr/local/share/applications",
-        user_apps.as_str(),
-        "/var/lib/flatpak/exports/share/applications",
-        flatpak_apps.as_str(),
-    ];
-
-    for search_path in search_paths {
-        if let Ok(entries) = fs::read_dir(search_path) {
-            for entry in entries.flatten() {
-                let path = entry.path();
-
-                if path.extension().and_then(|s| s.to_str()) == Some("desktop") {
-                    if let Ok(content) = fs::read_to_string(&path) {
-                        if let Some(app) = parse_desktop_file(&content, &query_lower) {
-                            if !seen_names.contains(&app.name) {
-                                seen_names.insert(app.name.clone());
-                                apps.push(app);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    apps.sort_by(|a, b| {
-        let a_exact = a.name.to_lowercase() == query_lower;
-        let b_exact = b.name.to_lowercase() == query_lower;
-
-        match (a_exact, b_exact) {
-            (true, false) => std::cmp::Ordering::Less,
-            (false, true) => std::cmp::Ordering::Greater,
-            _ => a.name.cmp(&b.name),
-        }
-    });
-
-    apps.truncate(10);
-
-    Ok(apps)
-}
-
-#[derive(Debug, Clone, serde::Serialize)]
-pub struct InstalledApp {
-    pub name: String,
-    pub display_name: String,
-    pub executable: String,
-    pub icon: Option<String>,
-    pub categories: Vec<String>,
-}
-
-fn parse_desktop_file(content: &str, query: &str) -> Option<InstalledApp> {
-    let mut name = None;
-    let mut exec = None;
-    let mut icon = None;
-    let mut categories = Vec::new();
-    let mut no_display = false;
-    let mut hidden = false;
-
-    for line in content.lines() {
-        let line = line.trim();
-
-        if line.starts_with("Name=") {
-            name = Some(line.strip_prefix("Name=")?.to_string());
-        } else if line.starts_with("Exec=") {
-            exec = Some(line.strip_prefix("Exec=")?.to_string());
-        } else if line.starts_with("Icon=") {
-            icon = Some(line.strip_prefix("Icon=")?.to_string());
-        } else if line.starts_with("Categories=") {
-            let cats = line.strip_prefix("Categories=")?;
-            categories = cats
-                .split(';')
-                .filter(|s| !s.is_empty())
-                .map(|s| s.to_string())
-                .collect();
-        } else if line.starts_with("NoDisplay=true") {
-            no_display = true;
-        } else if line.starts_with("Hidden=true") {
-            hidden = true;
-        }
-    }
-
-    if no_display || hidden {
-        return None;
-    }
-
-    let name = name?;
-    let exec = exec?;
-
-    let executable = exec
-        .split_whitespace()
-        .next()?
-        .trim_matches('"')
-        .split('/')
-        .last()?
-        .to_string();
-
-    if !name.to_lowercase().contains(query)
-        && !executable.to_lowercase().contains(query)
-        && !categories.iter().any(|c| c.to_lowercase().contains(query))
-    {
-        return None;
-    }
-
-    Some(InstalledApp {
-        display_name: name.clone(),
-        name: name.clone(),
-        executable,
-        icon,
-        categories,
-    })
-}
-
 fn show_block_notification(app_name: &str, icon: Option<&str>) {
-    #[cfg(target_os = "linux")]
-    {
-        let icon_arg = icon.unwrap_or("dialog-error");
-        let _ = Command::new("notify-send")
-            .arg("-u")
-            .arg("critical")
-            .arg("-t")
-            .arg("3000")
-            .arg("-i")
-            .arg(icon_arg)
-            .arg(format!("{} is blocked", app_name))
-            .arg(format!("Brisk is blocking {}", app_name))
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn();
-    }
+    notifications::notify(Notification {
+        kind: Kind::Blocked,
+        icon: icon.unwrap_or("dialog-error"),
+        summary: &format!("{} is blocked", app_name),
+        body: &format!("Brisk is blocking {}", app_name),
+        actions: &[Action::EndSession, Action::Snooze, Action::StartBreak],
+    });
 }
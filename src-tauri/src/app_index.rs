@@ -0,0 +1,632 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::app_blocker::AppSource;
+use crate::desktop_entry::{current_desktops, DesktopEntry};
+use crate::icon_theme::IconResolver;
+use crate::xdg;
+
+const REFRESH_DEBOUNCE: Duration = Duration::from_millis(500);
+pub const ICON_SIZE: u32 = 48;
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InstalledApp {
+    pub name: String,
+    pub display_name: String,
+    pub executable: String,
+    pub icon: Option<String>,
+    /// `icon` resolved through the icon theme as a data URL.
+    pub icon_url: Option<String>,
+    pub categories: Vec<String>,
+    pub startup_wm_class: Option<String>,
+    /// [`AppSource`] in its encoded form, for snaps and AppImages.
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct IndexedApp {
+    app: InstalledApp,
+    name_lower: String,
+    executable_lower: String,
+    generic_name_lower: Option<String>,
+    keywords_lower: Vec<String>,
+    categories_lower: Vec<String>,
+}
+
+/// In-memory index of installed applications, built once at startup and
+/// rebuilt whenever a watched applications directory changes.
+pub struct AppIndex {
+    apps: Arc<RwLock<Vec<IndexedApp>>>,
+    /// How often each lowercased executable was blocked. Kept in memory
+    /// only: session records already persist every block, so the counts are
+    /// rebuilt from them on each start with [`AppIndex::seed_usage`].
+    usage: Mutex<HashMap<String, u32>>,
+    icons: IconResolver,
+}
+
+impl AppIndex {
+    /// Starts out empty and fills in from a background scan, so startup
+    /// does not wait on reading every desktop entry.
+    pub fn new() -> Self {
+        let apps = Arc::new(RwLock::new(Vec::new()));
+
+        let scanned = Arc::clone(&apps);
+        thread::spawn(move || *scanned.write().unwrap() = build_index());
+
+        Self {
+            apps,
+            usage: Mutex::new(HashMap::new()),
+            icons: IconResolver::new(),
+        }
+    }
+
+    /// Starts watching the applications directories with inotify and
+    /// rebuilds the index shortly after any change. Directories that do
+    /// not exist yet are picked up once they are created.
+    pub fn watch(&self) -> Result<(), String> {
+        let (tx, rx) = mpsc::channel();
+
+        let targets: Vec<(PathBuf, RecursiveMode)> = application_dirs()
+            .into_iter()
+            .map(|dir| (dir, RecursiveMode::Recursive))
+            .chain(
+                appimage_dirs()
+                    .into_iter()
+                    .map(|dir| (dir, RecursiveMode::NonRecursive)),
+            )
+            .collect();
+        let dirs: Vec<PathBuf> = targets.iter().map(|(dir, _)| dir.clone()).collect();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                // Rescanning opens every file, so access events must not
+                // trigger another refresh, and neither should other files in
+                // the parents watched for directories yet to be created
+                if event.is_ok_and(|e| {
+                    !e.kind.is_access() && e.paths.iter().any(|p| concerns(p, &dirs))
+                }) {
+                    let _ = tx.send(());
+                }
+            })
+            .map_err(|e| format!("Failed to create applications watcher: {}", e))?;
+        add_watches(&mut watcher, &targets);
+
+        let apps = Arc::clone(&self.apps);
+        thread::spawn(move || {
+            while rx.recv().is_ok() {
+                // Package managers touch many files at once, so wait for
+                // the burst to settle before rescanning
+                while rx.recv_timeout(REFRESH_DEBOUNCE).is_ok() {}
+
+                add_watches(&mut watcher, &targets);
+                *apps.write().unwrap() = build_index();
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Adds previously used executables, e.g. from stored sessions, so
+    /// ranking reflects history from before this run.
+    pub fn seed_usage<I: IntoIterator<Item = String>>(&self, executables: I) {
+        let mut usage = self.usage.lock().unwrap();
+        for executable in executables {
+            *usage.entry(executable.to_lowercase()).or_insert(0) += 1;
+        }
+    }
+
+    /// Counts a block made during this run. Nothing is written here; the
+    /// session record it belongs to seeds it again after a restart.
+    pub fn record_usage(&self, executable: &str) {
+        let mut usage = self.usage.lock().unwrap();
+        *usage.entry(executable.to_lowercase()).or_insert(0) += 1;
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> Vec<InstalledApp> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+
+        let apps = self.apps.read().unwrap();
+        let usage = self.usage.lock().unwrap();
+
+        let mut matches: Vec<(u32, &IndexedApp)> = apps
+            .iter()
+            .filter_map(|app| {
+                let score = match_score(app, &query)?;
+                let used = usage.get(&app.executable_lower).copied().unwrap_or(0);
+                Some((score + used.min(10) * 30, app))
+            })
+            .collect();
+
+        matches.sort_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.app.name.cmp(&b.app.name))
+        });
+
+        matches
+            .into_iter()
+            .take(limit)
+            .map(|(_, app)| {
+                let mut app = app.app.clone();
+                app.icon_url = app
+                    .icon
+                    .as_deref()
+                    .and_then(|icon| self.icons.resolve_data_url(icon, ICON_SIZE));
+                app
+            })
+            .collect()
+    }
+
+    /// Resolves an icon name from the theme, or a path only when an
+    /// installed app's `Icon=` names it, so the webview cannot have any
+    /// image on disk read back to it.
+    pub fn resolve_icon(&self, icon: &str, size: u32) -> Option<String> {
+        let icon = icon.trim();
+        if icon.contains('/')
+            && !self
+                .apps
+                .read()
+                .unwrap()
+                .iter()
+                .any(|app| app.app.icon.as_deref() == Some(icon))
+        {
+            return None;
+        }
+
+        self.icons.resolve_data_url(icon, size)
+    }
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, each with `applications`
+/// appended, in precedence order. Flatpak and snap export directories are
+/// added when the session did not put them in `$XDG_DATA_DIRS`.
+pub fn application_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    let mut dirs = xdg::data_paths("applications");
+
+    let export_dirs = [
+        PathBuf::from(format!(
+            "{}/.local/share/flatpak/exports/share/applications",
+            home
+        )),
+        PathBuf::from("/var/lib/flatpak/exports/share/applications"),
+        PathBuf::from("/var/lib/snapd/desktop/applications"),
+    ];
+    for dir in export_dirs {
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+
+    dirs
+}
+
+/// Watches each directory, or its closest existing parent while it does
+/// not exist, so its creation is noticed.
+fn add_watches(watcher: &mut RecommendedWatcher, targets: &[(PathBuf, RecursiveMode)]) {
+    for (dir, mode) in targets {
+        let Some(existing) = dir.ancestors().find(|a| a.is_dir()) else {
+            continue;
+        };
+        let covered = || {
+            targets.iter().any(|(other, mode)| {
+                *mode == RecursiveMode::Recursive && other.is_dir() && existing.starts_with(other)
+            })
+        };
+
+        let mode = if existing == dir {
+            *mode
+        } else if covered() {
+            // A directory watched recursively already sees its
+            // subdirectories being created
+            continue;
+        } else {
+            RecursiveMode::NonRecursive
+        };
+        if let Err(e) = watcher.watch(existing, mode) {
+            eprintln!("Failed to watch {:?}: {}", existing, e);
+        }
+    }
+}
+
+/// Whether a change at `path` is inside one of `dirs` or creates one.
+fn concerns(path: &Path, dirs: &[PathBuf]) -> bool {
+    dirs.iter()
+        .any(|dir| path.starts_with(dir) || dir.starts_with(path))
+}
+
+/// Directories scanned for loose `.AppImage` files.
+pub fn appimage_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    vec![
+        PathBuf::from(format!("{}/Applications", home)),
+        PathBuf::from(format!("{}/.local/share/applications/appimages", home)),
+    ]
+}
+
+fn build_index() -> Vec<IndexedApp> {
+    let desktops = current_desktops();
+    let mut seen_ids = HashSet::new();
+    let mut seen_names = HashSet::new();
+    let mut seen_appimages = HashSet::new();
+    let mut apps = Vec::new();
+
+    for dir in application_dirs() {
+        let mut files = Vec::new();
+        collect_desktop_files(&dir, &dir, &mut files);
+
+        for (desktop_id, path) in files {
+            // The first directory that provides a desktop ID wins, even if
+            // that copy is hidden: this is how users mask system entries
+            if !seen_ids.insert(desktop_id) {
+                continue;
+            }
+
+            let Some(entry) = fs::read_to_string(&path)
+                .ok()
+                .and_then(|content| DesktopEntry::parse(&content))
+            else {
+                continue;
+            };
+
+            if !entry.is_application() || !entry.should_show_in(&desktops) || !entry.is_installed()
+            {
+                continue;
+            }
+
+            let Some(executable) = entry.executable() else {
+                continue;
+            };
+
+            if !seen_names.insert(entry.name.clone()) {
+                continue;
+            }
+
+            let source = app_source(&entry);
+            if let Some(AppSource::AppImage(path)) = &source {
+                seen_appimages.insert(PathBuf::from(path));
+            }
+
+            apps.push(IndexedApp {
+                name_lower: entry.name.to_lowercase(),
+                executable_lower: executable.to_lowercase(),
+                generic_name_lower: entry.generic_name.as_ref().map(|g| g.to_lowercase()),
+                keywords_lower: entry.keywords.iter().map(|k| k.to_lowercase()).collect(),
+                categories_lower: entry.categories.iter().map(|c| c.to_lowercase()).collect(),
+                app: InstalledApp {
+                    display_name: entry.name.clone(),
+                    name: entry.name,
+                    executable,
+                    icon: entry.icon,
+                    icon_url: None,
+                    categories: entry.categories,
+                    startup_wm_class: entry.startup_wm_class,
+                    source: source.map(|s| s.encode()),
+                },
+            });
+        }
+    }
+
+    // AppImages integrated by appimaged or AppImageLauncher already have a
+    // desktop entry pointing at the image, so only index the rest
+    for dir in appimage_dirs() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for path in entries.flatten().map(|e| e.path()) {
+            let is_appimage = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("appimage"));
+
+            if !is_appimage || !path.is_file() || seen_appimages.contains(&path) {
+                continue;
+            }
+
+            let (Some(file_name), Some(path_str)) =
+                (path.file_name().and_then(|n| n.to_str()), path.to_str())
+            else {
+                continue;
+            };
+
+            let name = appimage_display_name(file_name);
+            if !seen_names.insert(name.clone()) {
+                continue;
+            }
+
+            apps.push(IndexedApp {
+                name_lower: name.to_lowercase(),
+                executable_lower: file_name.to_lowercase(),
+                generic_name_lower: None,
+                keywords_lower: Vec::new(),
+                categories_lower: Vec::new(),
+                app: InstalledApp {
+                    display_name: name.clone(),
+                    name,
+                    executable: file_name.to_string(),
+                    icon: None,
+                    icon_url: None,
+                    categories: Vec::new(),
+                    startup_wm_class: None,
+                    source: Some(AppSource::AppImage(path_str.to_string()).encode()),
+                },
+            });
+        }
+    }
+
+    apps
+}
+
+fn app_source(entry: &DesktopEntry) -> Option<AppSource> {
+    if let Some(name) = &entry.snap_instance_name {
+        return Some(AppSource::Snap(name.clone()));
+    }
+
+    let program = entry.program()?;
+
+    // Snap commands are `/snap/bin/<snap>` or `/snap/bin/<snap>.<app>`
+    if let Some(command) = program.strip_prefix("/snap/bin/") {
+        let snap = command.split('.').next().unwrap_or(command);
+        return Some(AppSource::Snap(snap.to_string()));
+    }
+
+    let is_appimage = Path::new(&program)
+        .extension()
+        .and_then(|s| s.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("appimage"));
+
+    (is_appimage && program.starts_with('/')).then_some(AppSource::AppImage(program))
+}
+
+/// Turns `Obsidian-1.4.16-x86_64.AppImage` into `Obsidian`: the name is
+/// everything before the first version-like or architecture segment.
+fn appimage_display_name(file_name: &str) -> String {
+    let stem = file_name
+        .rsplit_once('.')
+        .map(|(stem, _)| stem)
+        .unwrap_or(file_name);
+
+    let segments: Vec<&str> = stem
+        .split(['-', '_'])
+        .take_while(|segment| !is_version_or_arch(segment))
+        .collect();
+
+    if segments.is_empty() {
+        stem.to_string()
+    } else {
+        segments.join(" ")
+    }
+}
+
+fn is_version_or_arch(segment: &str) -> bool {
+    let lower = segment.to_lowercase();
+    let version = lower.strip_prefix('v').unwrap_or(&lower);
+
+    version.starts_with(|c: char| c.is_ascii_digit())
+        || matches!(
+            lower.as_str(),
+            "x86" | "x64" | "amd64" | "aarch64" | "arm64" | "armhf" | "i386" | "i686"
+        )
+}
+
+/// Collects `.desktop` files below `dir` keyed by desktop file ID, where
+/// `kde4/okular.desktop` becomes `kde4-okular.desktop`.
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+
+        if path.is_dir() {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().and_then(|s| s.to_str()) == Some("desktop") {
+            if let Some(relative) = path.strip_prefix(root).ok().and_then(|p| p.to_str()) {
+                files.push((relative.replace('/', "-"), path.clone()));
+            }
+        }
+    }
+}
+
+fn match_score(app: &IndexedApp, query: &str) -> Option<u32> {
+    let name = &app.name_lower;
+
+    let score = if name == query {
+        1000
+    } else if name.starts_with(query) {
+        800
+    } else if name
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(query))
+    {
+        600
+    } else if name.contains(query) {
+        400
+    } else if app.executable_lower.starts_with(query) {
+        350
+    } else if app.executable_lower.contains(query) {
+        300
+    } else if app
+        .generic_name_lower
+        .as_ref()
+        .is_some_and(|g| g.contains(query))
+        || app.keywords_lower.iter().any(|k| k.starts_with(query))
+    {
+        200
+    } else if app.categories_lower.iter().any(|c| c.contains(query)) {
+        100
+    } else {
+        fuzzy_score(name, query)?
+    };
+
+    Some(score)
+}
+
+/// Scores `query` as an in-order subsequence of `text`, favoring runs of
+/// consecutive characters, so "vsc" finds "Visual Studio Code".
+fn fuzzy_score(text: &str, query: &str) -> Option<u32> {
+    let mut text_chars = text.chars();
+    let mut score = 0;
+    let mut run = 0;
+
+    for q in query.chars() {
+        let mut skipped = false;
+        loop {
+            let c = text_chars.next()?;
+            if c == q {
+                break;
+            }
+            skipped = true;
+        }
+        run = if skipped { 1 } else { run + 1 };
+        score += run;
+    }
+
+    Some(score.min(40) + 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(name: &str, executable: &str) -> IndexedApp {
+        IndexedApp {
+            name_lower: name.to_lowercase(),
+            executable_lower: executable.to_lowercase(),
+            generic_name_lower: None,
+            keywords_lower: Vec::new(),
+            categories_lower: Vec::new(),
+            app: InstalledApp {
+                name: name.to_string(),
+                display_name: name.to_string(),
+                executable: executable.to_string(),
+                icon: None,
+                icon_url: None,
+                categories: Vec::new(),
+                startup_wm_class: None,
+                source: None,
+            },
+        }
+    }
+
+    fn index(apps: Vec<IndexedApp>) -> AppIndex {
+        AppIndex {
+            apps: Arc::new(RwLock::new(apps)),
+            usage: Mutex::new(HashMap::new()),
+            icons: IconResolver::new(),
+        }
+    }
+
+    fn names(apps: Vec<InstalledApp>) -> Vec<String> {
+        apps.into_iter().map(|app| app.name).collect()
+    }
+
+    #[test]
+    fn exact_match_ranks_above_prefix_word_substring_and_fuzzy() {
+        let index = index(vec![
+            app("Cool Dev Editor", "cde"),
+            app("Barcode Reader", "barcode"),
+            app("Visual Studio Code", "vscode"),
+            app("Codeblocks", "codeblocks"),
+            app("Code", "code"),
+        ]);
+
+        assert_eq!(
+            names(index.search("code", 10)),
+            [
+                "Code",
+                "Codeblocks",
+                "Visual Studio Code",
+                "Barcode Reader",
+                "Cool Dev Editor"
+            ]
+        );
+    }
+
+    #[test]
+    fn executable_generic_name_keywords_and_categories_rank_below_the_name() {
+        let mut generic = app("Files", "nautilus");
+        generic.generic_name_lower = Some("file manager".to_string());
+        let mut keyword = app("Dolphin", "dolphin");
+        keyword.keywords_lower = vec!["manager".to_string()];
+        let mut category = app("Thunar", "thunar");
+        category.categories_lower = vec!["filemanager".to_string()];
+
+        assert_eq!(match_score(&app("Nautilus", "nautilus"), "naut"), Some(800));
+        assert_eq!(match_score(&app("Files", "nautilus"), "naut"), Some(350));
+        assert_eq!(
+            match_score(&app("Files", "org.gnome.nautilus"), "naut"),
+            Some(300)
+        );
+        assert_eq!(match_score(&generic, "manager"), Some(200));
+        assert_eq!(match_score(&keyword, "manager"), Some(200));
+        assert_eq!(match_score(&category, "manager"), Some(100));
+    }
+
+    #[test]
+    fn fuzzy_match_favors_consecutive_characters() {
+        assert!(fuzzy_score("visual studio code", "vsc").is_some());
+        assert!(fuzzy_score("abcdef", "abc") > fuzzy_score("axbxcx", "abc"));
+        assert_eq!(fuzzy_score("code", "edoc"), None);
+    }
+
+    #[test]
+    fn non_matching_and_empty_queries_find_nothing() {
+        let index = index(vec![app("Firefox", "firefox")]);
+
+        assert!(index.search("zzz", 10).is_empty());
+        assert!(index.search("xoferif", 10).is_empty());
+        assert!(index.search("  ", 10).is_empty());
+        assert_eq!(match_score(&app("Firefox", "firefox"), "zzz"), None);
+    }
+
+    #[test]
+    fn usage_breaks_ties_and_adds_up_to_a_cap() {
+        let index = index(vec![
+            app("Firewall", "firewall"),
+            app("Firefox", "firefox"),
+            app("Fire", "fire"),
+            app("Campfire", "campfire"),
+        ]);
+
+        // Equal scores fall back to the name
+        assert_eq!(
+            names(index.search("fire", 10)),
+            ["Fire", "Firefox", "Firewall", "Campfire"]
+        );
+
+        index.record_usage("FIREWALL");
+        assert_eq!(
+            names(index.search("fire", 10)),
+            ["Fire", "Firewall", "Firefox", "Campfire"]
+        );
+
+        // Ten uses are worth 300, enough for a prefix match to pass an
+        // exact one but not for a substring match to pass a prefix one
+        index.seed_usage((0..20).map(|_| "firefox".to_string()));
+        index.seed_usage((0..20).map(|_| "campfire".to_string()));
+        assert_eq!(
+            names(index.search("fire", 10)),
+            ["Firefox", "Fire", "Firewall", "Campfire"]
+        );
+    }
+
+    #[test]
+    fn results_are_limited() {
+        let index = index(vec![app("Fire", "fire"), app("Firefox", "firefox")]);
+
+        assert_eq!(names(index.search("fire", 1)), ["Fire"]);
+    }
+}
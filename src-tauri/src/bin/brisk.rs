@@ -0,0 +1,7 @@
+//! Command-line interface for starting and inspecting focus sessions
+//! without opening the window.
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    std::process::exit(brisq_lib::cli::run(&args));
+}
@@ -1,9 +1,25 @@
+use std::collections::HashSet;
 use std::fs;
 use std::process::Command;
 
-const HOSTS_FILE: &str = "/etc/hosts";
+use crate::connections::{self, ConnectionReport};
+use crate::dns::{self, FlushReport};
+
+pub const HOSTS_FILE: &str = "/etc/hosts";
 const BLOCK_MARKER: &str = "# Focus app blocked sites";
 
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct BlockReport {
+    pub dns: FlushReport,
+    pub connections: ConnectionReport,
+}
+
+impl BlockReport {
+    pub fn summary(&self) -> String {
+        format!("{}. {}", self.dns.summary(), self.connections.summary())
+    }
+}
+
 fn write_hosts_with_sudo(content: &str) -> Result<(), String> {
     let output = Command::new("sudo")
         .arg("-n")
@@ -48,9 +64,18 @@ fn write_hosts_with_sudo(content: &str) -> Result<(), String> {
     Ok(())
 }
 
-pub fn block_sites(sites: &[String]) -> Result<(), String> {
+pub fn block_sites(sites: &[String]) -> Result<BlockReport, String> {
+    // Rules with a URL path like youtube.com/shorts can only be enforced by
+    // the browser extension; blocking the host here would take the whole
+    // site down with them
+    let sites: Vec<String> = sites
+        .iter()
+        .filter(|s| SiteRule::parse(s).is_some_and(|rule| rule.path.is_none()))
+        .cloned()
+        .collect();
+
     if sites.is_empty() {
-        return Ok(());
+        return Ok(BlockReport::default());
     }
 
     let hosts_content = fs::read_to_string(HOSTS_FILE).map_err(|e| {
@@ -60,7 +85,8 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
         )
     })?;
 
-    let domains_to_block: Vec<String> = sites
+    // Sets keep this linear for imported lists with thousands of domains
+    let domains_to_block: HashSet<String> = sites
         .iter()
         .map(|s| extract_domain(s))
         .filter(|d| !d.is_empty())
@@ -76,12 +102,15 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
                 return false;
             }
             let line_trimmed = line.trim();
-            for domain in &domains_to_block {
-                if line_trimmed.contains(domain)
-                    && (line_trimmed.starts_with("0.0.0.0")
-                        || line_trimmed.starts_with("::1")
-                        || line_trimmed.starts_with("127.0.0.1"))
-                {
+            if line_trimmed.starts_with("0.0.0.0")
+                || line_trimmed.starts_with("::1")
+                || line_trimmed.starts_with("127.0.0.1")
+            {
+                let blocks_domain = line_trimmed.split_whitespace().skip(1).any(|host| {
+                    let host = host.strip_prefix("www.").unwrap_or(host);
+                    domains_to_block.contains(host)
+                });
+                if blocks_domain {
                     return false;
                 }
             }
@@ -92,10 +121,11 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
 
     lines.push(BLOCK_MARKER.to_string());
     let mut domains_added = Vec::new();
+    let mut seen = HashSet::new();
 
-    for site in sites {
+    for site in &sites {
         let domain = extract_domain(site);
-        if !domain.is_empty() && !domains_added.contains(&domain) {
+        if !domain.is_empty() && seen.insert(domain.clone()) {
             domains_added.push(domain.clone());
             let block_line_ipv4 = format!("0.0.0.0 {}", domain);
             let block_line_ipv6 = format!("::1 {}", domain);
@@ -113,6 +143,12 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
 
     println!("Total domains to block: {}", domains_added.len());
 
+    let hosts_to_block: Vec<String> = domains_added
+        .iter()
+        .flat_map(|domain| [domain.clone(), format!("www.{}", domain)])
+        .collect();
+    let addresses = connections::resolve_addresses(&hosts_to_block);
+
     let new_content = lines.join("\n");
     println!("Writing hosts file with {} lines", lines.len());
     println!("Domains being blocked: {:?}", domains_added);
@@ -153,52 +189,15 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
         }
     }
 
-    println!("Flushing DNS cache and restarting systemd-resolved...");
-
-    println!("Restarting systemd-resolved service...");
-    let restart_output = Command::new("sudo")
-        .arg("-n")
-        .arg("systemctl")
-        .arg("restart")
-        .arg("systemd-resolved")
-        .output();
-    match restart_output {
-        Ok(o) if o.status.success() => println!("? systemd-resolved restarted successfully"),
-        Ok(o) => println!(
-            "? systemd-resolved restart failed: {}",
-            String::from_utf8_lossy(&o.stderr)
-        ),
-        Err(e) => println!("? Could not restart systemd-resolved: {}", e),
+    let dns_report = dns::flush_caches();
+    if !dns_report.all_flushed() {
+        eprintln!("{}: {:?}", dns_report.summary(), dns_report.outcomes);
     }
 
-    // DNS flush commands - try without sudo first, then with passwordless sudo
-    let flush_commands = vec![
-        ("resolvectl", vec!["flush-caches"]),
-        ("systemd-resolve", vec!["--flush-caches"]),
-    ];
-
-    for (cmd, args) in flush_commands {
-        // Try without sudo first (some systems allow this)
-        let no_sudo_result = Command::new(cmd).args(&args).output();
-        if no_sudo_result.is_ok_and(|o| o.status.success()) {
-            continue; // Success without sudo
-        }
-        
-        // If that fails, use passwordless sudo
-        let _ = Command::new("sudo").arg("-n").arg(cmd).args(args).output();
-    }
-
-    // Try to restart nscd (not critical if it fails)
-    let _ = Command::new("sudo")
-        .arg("-n")
-        .arg("systemctl")
-        .arg("restart")
-        .arg("nscd")
-        .output();
-
-    println!("DNS cache flushed and systemd-resolved restarted");
-    println!("? IMPORTANT: Please restart your browser for site blocking to take effect!");
-    println!("   Browsers cache DNS and may not respect hosts file changes immediately.");
+    // Keep-alive connections opened before the hosts change would otherwise
+    // keep serving the blocked sites until the browser is restarted
+    let connection_report = connections::kill_connections(&addresses);
+    println!("{}", connection_report.summary());
 
     if !domains_added.is_empty() {
         let verify_content = fs::read_to_string(HOSTS_FILE).unwrap_or_default();
@@ -208,10 +207,13 @@ pub fn block_sites(sites: &[String]) -> Result<(), String> {
         }
     }
 
-    Ok(())
+    Ok(BlockReport {
+        dns: dns_report,
+        connections: connection_report,
+    })
 }
 
-pub fn unblock_sites() -> Result<(), String> {
+pub fn unblock_sites() -> Result<FlushReport, String> {
     let hosts_content =
         fs::read_to_string(HOSTS_FILE).map_err(|e| format!("Failed to read hosts file: {}", e))?;
 
@@ -281,57 +283,36 @@ pub fn unblock_sites() -> Result<(), String> {
     write_hosts_with_sudo(&new_content)?;
     println!("Hosts file updated successfully");
 
-    println!("Flushing DNS cache and restarting systemd-resolved...");
-
-    println!("Restarting systemd-resolved service...");
-    let restart_output = Command::new("sudo")
-        .arg("-n")
-        .arg("systemctl")
-        .arg("restart")
-        .arg("systemd-resolved")
-        .output();
-    match restart_output {
-        Ok(o) if o.status.success() => println!("? systemd-resolved restarted successfully"),
-        Ok(o) => println!(
-            "? systemd-resolved restart failed: {}",
-            String::from_utf8_lossy(&o.stderr)
-        ),
-        Err(e) => println!("? Could not restart systemd-resolved: {}", e),
-    }
-
-  
-    let flush_commands = vec![
-        ("resolvectl", vec!["flush-caches"]),
-        ("systemd-resolve", vec!["--flush-caches"]),
-    ];
-
-    for (cmd, args) in flush_commands {
-        // Try without sudo first (some systems allow this)
-        let no_sudo_result = Command::new(cmd).args(&args).output();
-        if no_sudo_result.is_ok_and(|o| o.status.success()) {
-            continue; // Success without sudo
-        }
-        
-        // If that fails, use passwordless sudo
-        let _ = Command::new("sudo").arg("-n").arg(cmd).args(args).output();
+    let report = dns::flush_caches();
+    if !report.all_flushed() {
+        eprintln!("{}: {:?}", report.summary(), report.outcomes);
     }
 
-    // Try to restart nscd (not critical if it fails)
-    let _ = Command::new("sudo")
-        .arg("-n")
-        .arg("systemctl")
-        .arg("restart")
-        .arg("nscd")
-        .output();
-
-    println!("DNS cache flushed and systemd-resolved restarted");
     println!("? IMPORTANT: You may need to refresh your browser or clear its DNS cache for sites to work immediately!");
     println!("   Some browsers cache DNS very aggressively. Try: Ctrl+Shift+R (hard refresh)");
 
-    Ok(())
+    Ok(report)
 }
 
-fn extract_domain(url_or_domain: &str) -> String {
+/// Whether `domain`, as returned by [`extract_domain`], is a hostname that
+/// can go in the hosts file: dot-separated labels of letters, digits,
+/// underscores and inner hyphens. Underscores are not valid in hostnames but
+/// do show up in DNS names that published blocklists list.
+pub(crate) fn is_valid_domain(domain: &str) -> bool {
+    domain.len() <= 253
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+}
+
+pub(crate) fn extract_domain(url_or_domain: &str) -> String {
     let mut cleaned = url_or_domain.trim().to_lowercase();
 
     if cleaned.starts_with("https://") {
@@ -359,3 +340,69 @@ fn extract_domain(url_or_domain: &str) -> String {
         .unwrap_or(&cleaned)
         .to_string()
 }
+
+/// A blocked site, optionally narrowed to URL paths. `path` may contain `*`
+/// wildcards, e.g. `youtube.com/shorts*`.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SiteRule {
+    pub domain: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+impl SiteRule {
+    pub fn parse(url_or_domain: &str) -> Option<Self> {
+        let domain = extract_domain(url_or_domain);
+        if domain.is_empty() {
+            return None;
+        }
+
+        let trimmed = url_or_domain.trim();
+        let without_scheme = trimmed
+            .split_once("://")
+            .map(|(_, rest)| rest)
+            .unwrap_or(trimmed);
+        let path = without_scheme
+            .split_once('/')
+            .map(|(_, path)| format!("/{}", path.trim_end_matches('/')))
+            .filter(|path| path != "/");
+
+        Some(Self { domain, path })
+    }
+
+    /// Whether a visited URL's host and path fall under this rule.
+    /// Subdomains of `domain` match too.
+    pub fn matches(&self, host: &str, path: &str) -> bool {
+        let host = host.trim_end_matches('.').to_lowercase();
+        let host_matches =
+            host == self.domain || host.ends_with(&format!(".{}", self.domain));
+
+        host_matches
+            && match &self.path {
+                Some(pattern) => path_matches(pattern, path),
+                None => true,
+            }
+    }
+}
+
+/// Matches `pattern` as a prefix of `path` on segment boundaries, with `*`
+/// matching any run of characters.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or("");
+
+    let Some(mut rest) = path.strip_prefix(first) else {
+        return false;
+    };
+
+    let mut ends_with_wildcard = false;
+    for part in parts {
+        ends_with_wildcard = part.is_empty();
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    ends_with_wildcard || rest.is_empty() || rest.starts_with(['/', '?', '#'])
+}
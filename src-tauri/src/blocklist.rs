@@ -0,0 +1,597 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::block::{extract_domain, is_valid_domain};
+use crate::blocklist_import::{self, SkippedLine};
+use crate::store;
+use crate::xdg;
+
+/// Prefix that marks a blocked entry as a whole category, e.g. `category:social`.
+pub const CATEGORY_PREFIX: &str = "category:";
+
+const BUILTIN_CATEGORIES: &[(&str, &str, &[&str])] = &[
+    (
+        "social",
+        "Social Networking",
+        &[
+            "facebook.com",
+            "m.facebook.com",
+            "fbcdn.net",
+            "facebook.net",
+            "messenger.com",
+            "instagram.com",
+            "cdninstagram.com",
+            "threads.net",
+            "twitter.com",
+            "mobile.twitter.com",
+            "x.com",
+            "twimg.com",
+            "t.co",
+            "reddit.com",
+            "old.reddit.com",
+            "redd.it",
+            "redditmedia.com",
+            "redditstatic.com",
+            "linkedin.com",
+            "licdn.com",
+            "pinterest.com",
+            "pinimg.com",
+            "tiktok.com",
+            "tiktokcdn.com",
+            "tiktokv.com",
+            "snapchat.com",
+            "sc-cdn.net",
+            "discord.com",
+            "discord.gg",
+            "discordapp.com",
+            "discordapp.net",
+            "tumblr.com",
+            "bsky.app",
+            "mastodon.social",
+            "telegram.org",
+            "web.telegram.org",
+            "web.whatsapp.com",
+        ],
+    ),
+    (
+        "video",
+        "Video & Streaming",
+        &[
+            "youtube.com",
+            "m.youtube.com",
+            "youtu.be",
+            "ytimg.com",
+            "youtube-nocookie.com",
+            "googlevideo.com",
+            "netflix.com",
+            "nflxvideo.net",
+            "nflximg.net",
+            "nflxext.com",
+            "twitch.tv",
+            "ttvnw.net",
+            "jtvnw.net",
+            "hulu.com",
+            "primevideo.com",
+            "disneyplus.com",
+            "max.com",
+            "vimeo.com",
+            "vimeocdn.com",
+            "dailymotion.com",
+            "crunchyroll.com",
+        ],
+    ),
+    (
+        "news",
+        "News",
+        &[
+            "news.google.com",
+            "news.ycombinator.com",
+            "cnn.com",
+            "edition.cnn.com",
+            "bbc.com",
+            "bbc.co.uk",
+            "nytimes.com",
+            "static01.nyt.com",
+            "theguardian.com",
+            "washingtonpost.com",
+            "wsj.com",
+            "bloomberg.com",
+            "reuters.com",
+            "apnews.com",
+            "foxnews.com",
+            "nbcnews.com",
+            "huffpost.com",
+            "buzzfeed.com",
+            "theverge.com",
+            "techcrunch.com",
+        ],
+    ),
+    (
+        "shopping",
+        "Shopping",
+        &[
+            "amazon.com",
+            "amazon.co.uk",
+            "amazon.de",
+            "amazon.in",
+            "m.media-amazon.com",
+            "ebay.com",
+            "ebaystatic.com",
+            "etsy.com",
+            "etsystatic.com",
+            "aliexpress.com",
+            "alicdn.com",
+            "walmart.com",
+            "target.com",
+            "bestbuy.com",
+            "temu.com",
+            "shein.com",
+            "flipkart.com",
+            "wish.com",
+        ],
+    ),
+    (
+        "gaming",
+        "Gaming",
+        &[
+            "store.steampowered.com",
+            "steamcommunity.com",
+            "steamstatic.com",
+            "epicgames.com",
+            "roblox.com",
+            "rbxcdn.com",
+            "chess.com",
+            "lichess.org",
+            "itch.io",
+            "poki.com",
+            "crazygames.com",
+            "miniclip.com",
+            "kongregate.com",
+            "ign.com",
+            "gamespot.com",
+        ],
+    ),
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub domains: Vec<String>,
+    /// Whether the category ships with Brisk. A custom category with the
+    /// same ID replaces the built-in one until it is deleted again.
+    #[serde(default)]
+    pub builtin: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportReport {
+    pub category: Category,
+    pub imported: usize,
+    pub duplicates: usize,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// The site categories users can block as a whole: the built-in presets
+/// plus custom categories persisted in the app data directory.
+pub struct BlocklistRegistry {
+    path: PathBuf,
+    custom: Mutex<Vec<Category>>,
+}
+
+impl BlocklistRegistry {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("blocklists.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let custom = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            custom: Mutex::new(custom),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Category> {
+        let custom = self.custom.lock().unwrap();
+
+        let mut categories: Vec<Category> = builtin_categories()
+            .into_iter()
+            .map(|builtin| {
+                custom
+                    .iter()
+                    .find(|c| c.id == builtin.id)
+                    .cloned()
+                    .unwrap_or(builtin)
+            })
+            .collect();
+
+        categories.extend(
+            custom
+                .iter()
+                .filter(|c| !c.builtin)
+                .cloned(),
+        );
+
+        categories
+    }
+
+    pub fn get(&self, id: &str) -> Option<Category> {
+        self.list().into_iter().find(|c| c.id == id)
+    }
+
+    pub fn create(&self, name: &str, domains: &[String]) -> Result<Category, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("Category name cannot be empty".to_string());
+        }
+
+        let existing = self.list();
+        let base_id = slugify(name, "category");
+        let mut id = base_id.clone();
+        let mut suffix = 2;
+        while existing.iter().any(|c| c.id == id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let category = Category {
+            id,
+            name: name.to_string(),
+            domains: normalize_domains(domains)?,
+            builtin: false,
+        };
+
+        let mut custom = self.custom.lock().unwrap();
+        custom.push(category.clone());
+        self.save(&custom)?;
+
+        Ok(category)
+    }
+
+    pub fn update(&self, id: &str, name: &str, domains: &[String]) -> Result<Category, String> {
+        let current = self
+            .get(id)
+            .ok_or_else(|| format!("No category with id {}", id))?;
+
+        let name = name.trim();
+        let category = Category {
+            id: current.id,
+            name: if name.is_empty() { current.name } else { name.to_string() },
+            domains: normalize_domains(domains)?,
+            builtin: current.builtin,
+        };
+
+        let mut custom = self.custom.lock().unwrap();
+        match custom.iter_mut().find(|c| c.id == id) {
+            Some(existing) => *existing = category.clone(),
+            None => custom.push(category.clone()),
+        }
+        self.save(&custom)?;
+
+        Ok(category)
+    }
+
+    /// Removes a custom category, or restores the defaults of an edited
+    /// built-in one.
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut custom = self.custom.lock().unwrap();
+        let before = custom.len();
+        custom.retain(|c| c.id != id);
+
+        if custom.len() == before {
+            return Err(if builtin_categories().iter().any(|c| c.id == id) {
+                "Built-in categories cannot be deleted".to_string()
+            } else {
+                format!("No category with id {}", id)
+            });
+        }
+
+        self.save(&custom)
+    }
+
+    /// Imports a local blocklist file as a new category. Accepts a category
+    /// shared as JSON (`{"name": ..., "domains": [...]}`) as well as hosts
+    /// files, plain domain lists and AdGuard/uBlock domain rules.
+    pub fn import(&self, path: &Path, name: Option<&str>) -> Result<ImportReport, String> {
+        #[derive(Deserialize)]
+        struct SharedCategory {
+            name: String,
+            domains: Vec<String>,
+        }
+
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+
+        let (default_name, parsed) = match serde_json::from_str::<SharedCategory>(&content) {
+            Ok(shared) => (
+                shared.name,
+                blocklist_import::parse(&shared.domains.join("\n")),
+            ),
+            Err(_) => (
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("Imported")
+                    .to_string(),
+                blocklist_import::parse(&content),
+            ),
+        };
+
+        if parsed.domains.is_empty() {
+            return Err(format!(
+                "No domains found in {:?} ({} lines skipped)",
+                path,
+                parsed.skipped.len()
+            ));
+        }
+
+        let name = name
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .unwrap_or(&default_name);
+        let category = self.create(name, &parsed.domains)?;
+
+        Ok(ImportReport {
+            imported: category.domains.len(),
+            category,
+            duplicates: parsed.duplicates,
+            skipped: parsed.skipped,
+        })
+    }
+
+    /// Replaces `category:<id>` entries with the category's domains and
+    /// passes other entries through, dropping duplicates.
+    pub fn expand(&self, blocked_things: &[String]) -> Vec<String> {
+        let mut expanded: Vec<String> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for thing in blocked_things {
+            let items = match thing.strip_prefix(CATEGORY_PREFIX) {
+                Some(id) => match self.get(id) {
+                    Some(category) => category.domains,
+                    None => {
+                        eprintln!("Unknown blocklist category: {}", id);
+                        Vec::new()
+                    }
+                },
+                None => vec![thing.clone()],
+            };
+
+            for item in items {
+                if seen.insert(item.clone()) {
+                    expanded.push(item);
+                }
+            }
+        }
+
+        expanded
+    }
+
+    fn save(&self, custom: &[Category]) -> Result<(), String> {
+        store::write(&self.path, custom)
+    }
+}
+
+fn builtin_categories() -> Vec<Category> {
+    BUILTIN_CATEGORIES
+        .iter()
+        .map(|(id, name, domains)| Category {
+            id: id.to_string(),
+            name: name.to_string(),
+            domains: domains.iter().map(|d| d.to_string()).collect(),
+            builtin: true,
+        })
+        .collect()
+}
+
+/// Runs each entry through [`extract_domain`] and rejects the list if any
+/// entry is not a usable domain, or if no domain is left.
+fn normalize_domains(domains: &[String]) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for entry in domains.iter().filter(|d| !d.trim().is_empty()) {
+        let domain = extract_domain(entry);
+        if !is_valid_domain(&domain) {
+            return Err(format!("Invalid domain: {}", entry));
+        }
+        if seen.insert(domain.clone()) {
+            normalized.push(domain);
+        }
+    }
+
+    if normalized.is_empty() {
+        return Err("A category needs at least one domain".to_string());
+    }
+    Ok(normalized)
+}
+
+/// Lowercase, hyphen-separated ID for a user-chosen name.
+pub(crate) fn slugify(name: &str, fallback: &str) -> String {
+    let slug = name
+        .to_lowercase()
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+
+    if slug.is_empty() {
+        fallback.to_string()
+    } else {
+        slug
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_path(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("brisk-blocklist-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("blocklists.json")
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn expand_replaces_categories_with_their_domains() {
+        let registry = BlocklistRegistry::load_from(store_path("expand"));
+        registry
+            .create("Work chat", &strings(&["slack.com", "example.com"]))
+            .unwrap();
+
+        let expanded = registry.expand(&strings(&[
+            "example.com",
+            "category:work-chat",
+            "category:missing",
+            "example.org/path",
+        ]));
+
+        assert_eq!(expanded, ["example.com", "slack.com", "example.org/path"]);
+    }
+
+    #[test]
+    fn expand_includes_builtin_categories() {
+        let registry = BlocklistRegistry::load_from(store_path("expand-builtin"));
+
+        let expanded = registry.expand(&strings(&["category:video"]));
+
+        assert!(expanded.contains(&"youtube.com".to_string()));
+        assert_eq!(expanded.len(), registry.get("video").unwrap().domains.len());
+    }
+
+    #[test]
+    fn custom_categories_persist_across_loads() {
+        let path = store_path("round-trip");
+        let registry = BlocklistRegistry::load_from(path.clone());
+
+        let created = registry
+            .create(
+                " Forums ",
+                &strings(&["https://www.forum.example.com/", "FORUM.example.com", " "]),
+            )
+            .unwrap();
+
+        assert_eq!(created.id, "forums");
+        assert_eq!(created.name, "Forums");
+        assert_eq!(created.domains, ["forum.example.com"]);
+        assert_eq!(
+            BlocklistRegistry::load_from(path).get("forums"),
+            Some(created)
+        );
+    }
+
+    #[test]
+    fn ids_do_not_clash_with_existing_categories() {
+        let registry = BlocklistRegistry::load_from(store_path("ids"));
+
+        let social = registry
+            .create("Social", &strings(&["example.com"]))
+            .unwrap();
+        let again = registry
+            .create("social", &strings(&["example.com"]))
+            .unwrap();
+
+        assert_eq!(social.id, "social-2");
+        assert_eq!(again.id, "social-3");
+    }
+
+    #[test]
+    fn editing_a_builtin_category_overrides_it_until_deleted() {
+        let path = store_path("builtin");
+        let registry = BlocklistRegistry::load_from(path.clone());
+
+        let edited = registry
+            .update("news", "", &strings(&["news.example.com"]))
+            .unwrap();
+
+        assert_eq!(edited.name, "News");
+        assert!(edited.builtin);
+        let reloaded = BlocklistRegistry::load_from(path);
+        assert_eq!(reloaded.get("news"), Some(edited));
+        assert_eq!(reloaded.list().iter().filter(|c| c.id == "news").count(), 1);
+
+        reloaded.delete("news").unwrap();
+        assert!(reloaded.get("news").unwrap().domains.len() > 1);
+        assert!(reloaded.delete("news").is_err());
+    }
+
+    #[test]
+    fn create_and_update_reject_unusable_input() {
+        let path = store_path("invalid");
+        let registry = BlocklistRegistry::load_from(path.clone());
+
+        assert!(registry.create("  ", &strings(&["example.com"])).is_err());
+        assert!(registry.create("Empty", &[]).is_err());
+        assert!(registry.create("Blank", &strings(&["", "  "])).is_err());
+        assert!(registry
+            .create("Invalid", &strings(&["example.com", "not a domain"]))
+            .is_err());
+        assert!(!path.exists());
+
+        registry
+            .create("Valid", &strings(&["example.com"]))
+            .unwrap();
+        assert!(registry.update("valid", "Valid", &[]).is_err());
+        assert!(registry
+            .update("valid", "Valid", &strings(&["bad_domain"]))
+            .is_err());
+        assert!(registry
+            .update("missing", "Missing", &strings(&["example.com"]))
+            .is_err());
+        assert_eq!(registry.get("valid").unwrap().domains, ["example.com"]);
+    }
+
+    #[test]
+    fn delete_removes_custom_categories() {
+        let registry = BlocklistRegistry::load_from(store_path("delete"));
+        registry
+            .create("Forums", &strings(&["example.com"]))
+            .unwrap();
+
+        registry.delete("forums").unwrap();
+
+        assert_eq!(registry.get("forums"), None);
+        assert!(registry.delete("forums").is_err());
+        assert!(registry.delete("social").is_err());
+    }
+
+    #[test]
+    fn imports_hosts_files_and_shared_categories() {
+        let path = store_path("import");
+        let registry = BlocklistRegistry::load_from(path.clone());
+        let dir = path.parent().unwrap();
+        fs::create_dir_all(dir).unwrap();
+
+        let hosts = dir.join("ad servers.txt");
+        fs::write(
+            &hosts,
+            "0.0.0.0 ads.example.com\n0.0.0.0 ads.example.com\nbad line here\n",
+        )
+        .unwrap();
+        let report = registry.import(&hosts, None).unwrap();
+        assert_eq!(report.category.name, "ad servers");
+        assert_eq!(report.category.domains, ["ads.example.com"]);
+        assert_eq!((report.imported, report.duplicates), (1, 1));
+        assert_eq!(report.skipped.len(), 1);
+
+        let shared = dir.join("shared.json");
+        fs::write(&shared, r#"{"name": "Shared", "domains": ["example.org"]}"#).unwrap();
+        let report = registry.import(&shared, Some("Renamed")).unwrap();
+        assert_eq!(report.category.name, "Renamed");
+        assert_eq!(report.category.domains, ["example.org"]);
+
+        let empty = dir.join("empty.txt");
+        fs::write(&empty, "# nothing here\n").unwrap();
+        assert!(registry.import(&empty, None).is_err());
+    }
+}
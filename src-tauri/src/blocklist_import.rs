@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::block::{extract_domain, is_valid_domain};
+
+/// Names that hosts files map to loopback for the system itself rather
+/// than to block anything.
+const LOCAL_HOSTNAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedLine {
+    pub line: usize,
+    pub content: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ParsedBlocklist {
+    pub domains: Vec<String>,
+    pub duplicates: usize,
+    pub skipped: Vec<SkippedLine>,
+}
+
+/// Parses a blocklist in any mix of hosts-file (`0.0.0.0 example.com`),
+/// one-domain-per-line and AdGuard/uBlock (`||example.com^`) syntax.
+/// Comments and blank lines are ignored; anything else that does not yield
+/// a valid domain is reported in `skipped`.
+pub fn parse(content: &str) -> ParsedBlocklist {
+    let mut parsed = ParsedBlocklist::default();
+    let mut seen = HashSet::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim();
+
+        if line.is_empty()
+            || line.starts_with('#')
+            || line.starts_with('!')
+            || (line.starts_with('[') && line.ends_with(']'))
+        {
+            continue;
+        }
+
+        let mut skip = |reason: &str| {
+            parsed.skipped.push(SkippedLine {
+                line: index + 1,
+                content: raw.to_string(),
+                reason: reason.to_string(),
+            });
+        };
+
+        let candidates = match parse_line(line) {
+            Ok(candidates) => candidates,
+            Err(reason) => {
+                skip(reason);
+                continue;
+            }
+        };
+
+        for candidate in candidates {
+            if LOCAL_HOSTNAMES.contains(&candidate) {
+                continue;
+            }
+
+            let domain = extract_domain(candidate.trim_start_matches("*."));
+            if !is_valid_domain(&domain) {
+                skip("Not a valid domain");
+                continue;
+            }
+
+            if seen.insert(domain.clone()) {
+                parsed.domains.push(domain);
+            } else {
+                parsed.duplicates += 1;
+            }
+        }
+    }
+
+    parsed
+}
+
+/// Returns the hostnames a single non-comment line names.
+fn parse_line(line: &str) -> Result<Vec<&str>, &'static str> {
+    if line.starts_with("@@") {
+        return Err("Exception rules are not supported");
+    }
+    if line.contains("##") || line.contains("#@#") || line.contains("#?#") {
+        return Err("Cosmetic filters are not supported");
+    }
+
+    if let Some(rule) = line.strip_prefix("||") {
+        if rule.contains('$') {
+            return Err("Rule options are not supported");
+        }
+        let host = rule.trim_end_matches('|').trim_end_matches('^');
+        if host.contains(['/', '^', '*']) {
+            return Err("Only whole-domain rules are supported");
+        }
+        return Ok(vec![host]);
+    }
+
+    if line.starts_with('|') || line.starts_with('/') {
+        return Err("Only whole-domain rules are supported");
+    }
+
+    // Strip trailing comments after the entry itself, which may follow a
+    // space or a tab
+    let line = line
+        .match_indices('#')
+        .find(|(i, _)| line[..*i].ends_with(char::is_whitespace))
+        .map_or(line, |(i, _)| &line[..i])
+        .trim();
+    let mut fields = line.split_whitespace();
+    let first = fields.next().ok_or("Empty line")?;
+
+    if first.parse::<std::net::IpAddr>().is_ok() {
+        let hosts: Vec<&str> = fields.collect();
+        if hosts.is_empty() {
+            return Err("Hosts entry without a hostname");
+        }
+        return Ok(hosts);
+    }
+
+    if fields.next().is_some() {
+        return Err("Unrecognized line format");
+    }
+
+    Ok(vec![first])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reasons(parsed: &ParsedBlocklist) -> Vec<(usize, &str)> {
+        parsed
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.line, skipped.reason.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn hosts_entries_yield_their_hostnames() {
+        let parsed = parse(
+            "0.0.0.0 ads.example.com\n\
+             127.0.0.1\ttracker.example.com\n\
+             0.0.0.0 one.example.com two.example.com\n\
+             ::1 ipv6.example.com\n",
+        );
+
+        assert_eq!(
+            parsed.domains,
+            [
+                "ads.example.com",
+                "tracker.example.com",
+                "one.example.com",
+                "two.example.com",
+                "ipv6.example.com"
+            ]
+        );
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn trailing_comments_are_stripped() {
+        let parsed = parse(
+            "0.0.0.0 ads.example.com # ad server\n\
+             tracker.example.com\t#tracker\n\
+             0.0.0.0 anchor.example.com#not-a-comment\n",
+        );
+
+        assert_eq!(parsed.domains, ["ads.example.com", "tracker.example.com"]);
+        assert_eq!(reasons(&parsed), [(3, "Not a valid domain")]);
+    }
+
+    #[test]
+    fn loopback_entries_for_the_system_are_ignored() {
+        let parsed = parse(
+            "127.0.0.1 localhost\n\
+             127.0.0.1 localhost.localdomain local\n\
+             255.255.255.255 broadcasthost\n\
+             ::1 ip6-localhost ip6-loopback\n\
+             0.0.0.0 0.0.0.0\n\
+             0.0.0.0 ads.example.com\n",
+        );
+
+        assert_eq!(parsed.domains, ["ads.example.com"]);
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn plain_domains_and_urls_are_normalized() {
+        let parsed = parse(
+            "Example.com\n\
+             *.wildcard.example.com\n\
+             https://www.news.example.com/path\n\
+             sub.example.org:8080\n",
+        );
+
+        assert_eq!(
+            parsed.domains,
+            [
+                "example.com",
+                "wildcard.example.com",
+                "news.example.com",
+                "sub.example.org"
+            ]
+        );
+    }
+
+    #[test]
+    fn adguard_domain_rules_yield_their_domain() {
+        let parsed = parse(
+            "||ads.example.com^\n\
+             ||tracker.example.com^|\n\
+             ||plain.example.com\n",
+        );
+
+        assert_eq!(
+            parsed.domains,
+            [
+                "ads.example.com",
+                "tracker.example.com",
+                "plain.example.com"
+            ]
+        );
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn adguard_rules_with_modifiers_or_exceptions_are_skipped() {
+        let parsed = parse(
+            "||ads.example.com^$third-party\n\
+             @@||allowed.example.com^\n\
+             ||example.com/ads^\n\
+             ||ads*.example.com^\n\
+             |https://example.com/banner\n\
+             /banner[0-9]+/\n",
+        );
+
+        assert!(parsed.domains.is_empty());
+        assert_eq!(
+            reasons(&parsed),
+            [
+                (1, "Rule options are not supported"),
+                (2, "Exception rules are not supported"),
+                (3, "Only whole-domain rules are supported"),
+                (4, "Only whole-domain rules are supported"),
+                (5, "Only whole-domain rules are supported"),
+                (6, "Only whole-domain rules are supported"),
+            ]
+        );
+    }
+
+    #[test]
+    fn comments_blank_lines_and_section_headers_are_ignored() {
+        let parsed = parse(
+            "# hosts comment\n\
+             ! adblock comment\n\
+             [Adblock Plus 2.0]\n\
+             \n   \n\
+             example.com\n",
+        );
+
+        assert_eq!(parsed.domains, ["example.com"]);
+        assert!(parsed.skipped.is_empty());
+    }
+
+    #[test]
+    fn duplicates_across_formats_are_counted_once() {
+        let parsed = parse(
+            "example.com\n\
+             0.0.0.0 example.com\n\
+             ||example.com^\n\
+             www.example.com\n\
+             other.example.com\n",
+        );
+
+        assert_eq!(parsed.domains, ["example.com", "other.example.com"]);
+        assert_eq!(parsed.duplicates, 3);
+    }
+
+    #[test]
+    fn unusable_lines_are_reported_with_a_reason() {
+        let parsed = parse(
+            "example.com##.banner\n\
+             example.com#@#.banner\n\
+             example.com#?#div:has(.ad)\n\
+             0.0.0.0\n\
+             example.com other.com\n\
+             not_a_domain\n\
+             bad-.example.com\n",
+        );
+
+        assert!(parsed.domains.is_empty());
+        assert_eq!(
+            reasons(&parsed),
+            [
+                (1, "Cosmetic filters are not supported"),
+                (2, "Cosmetic filters are not supported"),
+                (3, "Cosmetic filters are not supported"),
+                (4, "Hosts entry without a hostname"),
+                (5, "Unrecognized line format"),
+                (6, "Not a valid domain"),
+                (7, "Not a valid domain"),
+            ]
+        );
+        assert_eq!(parsed.skipped[4].content, "example.com other.com");
+    }
+
+    #[test]
+    fn parse_line_splits_hosts_entries() {
+        assert_eq!(
+            parse_line("0.0.0.0\ta.example.com  b.example.com"),
+            Ok(vec!["a.example.com", "b.example.com"])
+        );
+        assert_eq!(parse_line("example.com"), Ok(vec!["example.com"]));
+        assert_eq!(parse_line("||example.com^"), Ok(vec!["example.com"]));
+    }
+}
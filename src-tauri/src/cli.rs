@@ -0,0 +1,543 @@
+use std::fs::{self, OpenOptions};
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{Local, TimeZone, Utc};
+
+use crate::app_blocker::{AppBlocker, BlockedApp};
+use crate::block;
+use crate::blocklist::BlocklistRegistry;
+use crate::commands::{self, SessionRequest};
+use crate::dbus;
+use crate::dnd::Dnd;
+use crate::hooks::{self, HookEvent};
+use crate::profiles::{EnforcementMode, ProfileStore};
+use crate::reminders::ReminderSettings;
+use crate::session::{ActiveSession, SessionState};
+use crate::xdg;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_HISTORY_LIMIT: usize = 10;
+/// Run by `brisk start` in the background; not meant to be typed.
+const ENFORCE_COMMAND: &str = "enforce";
+
+const USAGE: &str = "\
+Usage: brisk <command> [options]
+
+Commands:
+  start     Start a focus session, through the app when it is running
+              --goal <text>          What the session is for
+              --duration <length>    e.g. 90s, 25m or 2h; plain numbers are minutes
+              --site <rule>          Site, URL rule or category:<id> to block (repeatable)
+              --app <executable>     App to block (repeatable)
+              --profile <id>         Start from a saved profile
+              --strict               Refuse to stop before the session ends
+  stop      End the active session
+  status    Show the active session
+  history   List past sessions
+              --limit <n>            Number of sessions to show (default 10)
+  profiles  List saved profiles
+
+Add --json to status, history or profiles for machine-readable output.";
+
+/// Entry point of the `brisk` binary. Returns the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    let Some(command) = args.get(1) else {
+        eprintln!("{}", USAGE);
+        return 2;
+    };
+    let options = &args[2..];
+
+    let result = match command.as_str() {
+        "start" => start(options),
+        "stop" => stop(),
+        ENFORCE_COMMAND => enforce(),
+        "status" => status(options),
+        "history" => history(options),
+        "profiles" => profiles(options),
+        "help" | "--help" | "-h" => {
+            println!("{}", USAGE);
+            return 0;
+        }
+        other => {
+            eprintln!("Unknown command: {}\n\n{}", other, USAGE);
+            return 2;
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("brisk: {}", e);
+            1
+        }
+    }
+}
+
+/// Values of every `--name <value>` occurrence in `options`. Each value is
+/// consumed with its name; a missing one, or one that is another option,
+/// is an error rather than being read as the value.
+fn values<'a>(options: &'a [String], name: &str) -> Result<Vec<&'a str>, String> {
+    let mut values = Vec::new();
+    let mut options = options.iter();
+
+    while let Some(option) = options.next() {
+        if option != name {
+            continue;
+        }
+        match options.next() {
+            Some(value) if !value.starts_with("--") => values.push(value.as_str()),
+            _ => return Err(format!("{} needs a value", name)),
+        }
+    }
+
+    Ok(values)
+}
+
+fn value<'a>(options: &'a [String], name: &str) -> Result<Option<&'a str>, String> {
+    Ok(values(options, name)?.into_iter().last())
+}
+
+fn flag(options: &[String], name: &str) -> bool {
+    options.iter().any(|o| o == name)
+}
+
+/// Parses `90s`, `25m` or `2h` into seconds. A bare number is minutes.
+fn parse_duration(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => (&value[..i], c),
+        _ => (value, 'm'),
+    };
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("Invalid duration: {}", value))?;
+    let seconds = match unit {
+        's' => Some(number),
+        'm' => number.checked_mul(60),
+        'h' => number.checked_mul(3600),
+        _ => return Err(format!("Invalid duration unit in {}", value)),
+    }
+    .ok_or_else(|| format!("Duration is too long: {}", value))?;
+
+    if seconds == 0 {
+        return Err("Duration must be greater than zero".to_string());
+    }
+    Ok(seconds)
+}
+
+fn format_time(timestamp: i64) -> String {
+    Local
+        .timestamp_opt(timestamp, 0)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Starts a session through the running app, or, when it is not running,
+/// blocks sites here and leaves a background process to enforce the rest.
+fn start(options: &[String]) -> Result<(), String> {
+    let request = request_from(options)?;
+
+    if let Some(app) = dbus::connect_to_app() {
+        let message = app
+            .start_session_with_options(
+                &request.goal,
+                request.duration,
+                &request.blocked_things,
+                &request.blocked_apps,
+                request.profile.as_deref().unwrap_or_default(),
+                request.enforcement == EnforcementMode::Strict,
+            )
+            .map_err(dbus::reply_error)?;
+        println!("{}", message);
+        return Ok(());
+    }
+
+    let session_state = SessionState::load();
+    if session_state.current().is_some() {
+        return Err("A session is already running".to_string());
+    }
+
+    let started_at = Utc::now().timestamp();
+    let sites = BlocklistRegistry::load().expand(&request.blocked_things);
+    if !sites.is_empty() {
+        let report =
+            block::block_sites(&sites).map_err(|e| format!("Failed to block sites: {}", e))?;
+        println!("{}", report.summary());
+    }
+
+    // Without a session nothing would ever lift the blocking
+    let active = commands::record_session(request, started_at).inspect_err(|_| {
+        let _ = block::unblock_sites();
+    })?;
+    if let Err(e) = session_state.begin(active.clone()) {
+        let _ = fs::remove_file(&active.record);
+        let _ = block::unblock_sites();
+        return Err(e);
+    }
+
+    if let Err(e) = spawn_enforcer() {
+        // Without the enforcer nothing would ever lift the blocking
+        let _ = block::unblock_sites();
+        commands::finish_session(&session_state);
+        return Err(e);
+    }
+    println!(
+        "Focusing on \"{}\" until {}",
+        active.goal,
+        format_time(active.ends_at)
+    );
+    Ok(())
+}
+
+/// Builds the session `brisk start` asks for from its options.
+fn request_from(options: &[String]) -> Result<SessionRequest, String> {
+    let mut request = match value(options, "--profile")? {
+        Some(id) => SessionRequest::from_profile(
+            ProfileStore::load()
+                .get(id)
+                .ok_or_else(|| format!("No profile with id {}", id))?,
+        ),
+        None => SessionRequest {
+            goal: "Focus".to_string(),
+            duration: 25 * 60,
+            blocked_things: Vec::new(),
+            blocked_apps: Vec::new(),
+            profile: None,
+            enforcement: EnforcementMode::default(),
+            schedule: None,
+            pomodoro: None,
+            reminders: ReminderSettings::default(),
+        },
+    };
+
+    if let Some(goal) = value(options, "--goal")?.filter(|g| !g.trim().is_empty()) {
+        request.goal = goal.to_string();
+    }
+    if let Some(duration) = value(options, "--duration")? {
+        request.duration = parse_duration(duration)?;
+    }
+    request
+        .blocked_things
+        .extend(values(options, "--site")?.into_iter().map(String::from));
+    // The CLI takes bare executables; the stored form also carries a
+    // display name and icon
+    request.blocked_apps.extend(
+        values(options, "--app")?
+            .into_iter()
+            .map(|exe| format!("{}|||{}", exe, exe)),
+    );
+    if flag(options, "--strict") {
+        request.enforcement = EnforcementMode::Strict;
+    }
+
+    Ok(request)
+}
+
+/// Runs `brisk enforce` in its own process group, so closing the terminal
+/// or pressing Ctrl-C there does not leave the session half enforced.
+fn spawn_enforcer() -> Result<(), String> {
+    let exe = std::env::current_exe()
+        .map_err(|e| format!("Failed to find the brisk executable: {}", e))?;
+
+    let log_path = xdg::app_data_dir().join("cli.log");
+    if let Some(dir) = log_path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .map_err(|e| format!("Failed to open {:?}: {}", log_path, e))?;
+    let log_err = log
+        .try_clone()
+        .map_err(|e| format!("Failed to open {:?}: {}", log_path, e))?;
+
+    Command::new(exe)
+        .arg(ENFORCE_COMMAND)
+        .process_group(0)
+        .stdin(Stdio::null())
+        .stdout(log)
+        .stderr(log_err)
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to start enforcing the session: {}", e))
+}
+
+/// Blocks the active session's apps and silences notifications until the
+/// session ends or is stopped, then lifts all blocking.
+fn enforce() -> Result<(), String> {
+    let active = SessionState::load().current().ok_or("No active session")?;
+
+    let app_blocker = AppBlocker::new();
+    let apps: Vec<BlockedApp> = active
+        .blocked_apps
+        .iter()
+        .map(|app| BlockedApp::from_encoded(app))
+        .collect();
+    if !apps.is_empty() {
+        app_blocker.start_blocking(apps)?;
+    }
+    let dnd = Dnd::load();
+    if let Err(e) = dnd.enable() {
+        eprintln!("Failed to turn on Do-Not-Disturb: {}", e);
+    }
+    hooks::spawn(HookEvent::SessionStart, Some(active.clone()), None);
+
+    loop {
+        thread::sleep(POLL_INTERVAL);
+
+        // `brisk stop` or the app ended the session
+        let current = SessionState::load().current();
+        if current.map(|s| s.started_at) != Some(active.started_at) {
+            break;
+        }
+
+        if Utc::now().timestamp() >= active.ends_at {
+            block::unblock_sites().map_err(|e| format!("Failed to unblock sites: {}", e))?;
+            commands::finish_session(&SessionState::load());
+            println!("Session complete");
+            hooks::run(HookEvent::SessionEnd, Some(&active), None);
+            break;
+        }
+    }
+
+    // After `brisk stop` this only releases what this process holds
+    if let Err(e) = dnd.restore() {
+        eprintln!("Failed to restore notification settings: {}", e);
+    }
+    app_blocker.stop_blocking()
+}
+
+/// Ends the active session through the running app, or directly when it
+/// is not running.
+fn stop() -> Result<(), String> {
+    if let Some(app) = dbus::connect_to_app() {
+        let message = app.stop_session().map_err(dbus::reply_error)?;
+        println!("{}", message);
+        return Ok(());
+    }
+
+    let session_state = SessionState::load();
+    let session = session_state.current().ok_or("No active session")?;
+    session_state.ensure_unlocked(Utc::now().timestamp())?;
+
+    let report = block::unblock_sites().map_err(|e| format!("Failed to unblock sites: {}", e))?;
+    if let Err(e) = Dnd::load().restore() {
+        eprintln!("Failed to restore notification settings: {}", e);
+    }
+    commands::finish_session(&session_state);
+
+    println!("Ended \"{}\". {}", session.goal, report.summary());
+    hooks::run(HookEvent::SessionEnd, Some(&session), None);
+    Ok(())
+}
+
+/// The active session as the running app sees it, or as last saved when
+/// it is not running.
+fn current_session() -> Result<Option<ActiveSession>, String> {
+    match dbus::connect_to_app() {
+        Some(app) => {
+            let json = app.get_session().map_err(dbus::reply_error)?;
+            serde_json::from_str(&json).map_err(|e| format!("Failed to parse session: {}", e))
+        }
+        None => Ok(SessionState::load().current()),
+    }
+}
+
+fn status(options: &[String]) -> Result<(), String> {
+    let current = current_session()?;
+
+    if flag(options, "--json") {
+        let json = serde_json::to_string_pretty(&current)
+            .map_err(|e| format!("Failed to serialize {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    match current {
+        Some(session) => print_session(&session),
+        None => println!("No active session"),
+    }
+    Ok(())
+}
+
+fn print_session(session: &ActiveSession) {
+    let now = Utc::now().timestamp();
+    let remaining = (session.ends_at - now).max(0);
+
+    println!("{}", session.goal);
+    println!(
+        "  {} left, ends at {}",
+        format_remaining(remaining),
+        format_time(session.ends_at)
+    );
+    if session.pause.is_some() {
+        println!("  paused");
+    }
+    if session.is_locked(now) {
+        println!("  strict mode");
+    }
+    if !session.blocked_things.is_empty() {
+        println!("  sites: {}", session.blocked_things.join(", "));
+    }
+    if !session.blocked_apps.is_empty() {
+        let apps: Vec<String> = session
+            .blocked_apps
+            .iter()
+            .map(|app| BlockedApp::from_encoded(app).name)
+            .collect();
+        println!("  apps: {}", apps.join(", "));
+    }
+}
+
+fn format_remaining(seconds: i64) -> String {
+    if seconds >= 3600 {
+        format!("{}h {}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m {}s", seconds / 60, seconds % 60)
+    }
+}
+
+fn history(options: &[String]) -> Result<(), String> {
+    let limit = match value(options, "--limit")? {
+        Some(limit) => limit
+            .parse()
+            .map_err(|_| format!("Invalid limit: {}", limit))?,
+        None => DEFAULT_HISTORY_LIMIT,
+    };
+
+    let sessions: Vec<_> = commands::get_all_sessions()?
+        .into_iter()
+        .take(limit)
+        .collect();
+
+    if flag(options, "--json") {
+        let json = serde_json::to_string_pretty(&sessions)
+            .map_err(|e| format!("Failed to serialize {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for session in &sessions {
+        println!(
+            "{}  {:>4} min  {}",
+            format_time(session.timestamp),
+            session.duration.saturating_sub(session.paused_duration) / 60,
+            session.goal
+        );
+    }
+    Ok(())
+}
+
+fn profiles(options: &[String]) -> Result<(), String> {
+    let profiles = ProfileStore::load().list();
+
+    if flag(options, "--json") {
+        let json = serde_json::to_string_pretty(&profiles)
+            .map_err(|e| format!("Failed to serialize {}", e))?;
+        println!("{}", json);
+        return Ok(());
+    }
+
+    for profile in &profiles {
+        println!(
+            "{}  {} ({} min{})",
+            profile.id,
+            profile.name,
+            profile.duration / 60,
+            if profile.enforcement == EnforcementMode::Strict {
+                ", strict"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(options: &[&str]) -> Vec<String> {
+        options.iter().map(|o| o.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_durations_in_each_unit() {
+        assert_eq!(parse_duration("90s"), Ok(90));
+        assert_eq!(parse_duration("25m"), Ok(25 * 60));
+        assert_eq!(parse_duration("2h"), Ok(2 * 3600));
+        assert_eq!(parse_duration("45"), Ok(45 * 60));
+        assert_eq!(parse_duration(" 10m "), Ok(10 * 60));
+    }
+
+    #[test]
+    fn rejects_invalid_durations() {
+        for value in ["", "m", "abc", "-5m", "1.5h", "10d", "0", "0s", "0h"] {
+            assert!(parse_duration(value).is_err(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_durations_that_overflow() {
+        let minutes = format!("{}m", u64::MAX / 60 + 1);
+        let hours = format!("{}h", u64::MAX / 3600 + 1);
+
+        assert!(parse_duration(&minutes).is_err());
+        assert!(parse_duration(&hours).is_err());
+        assert_eq!(parse_duration(&format!("{}s", u64::MAX)), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn collects_every_value_of_a_repeated_option() {
+        let options = args(&["--site", "a.com", "--app", "firefox", "--site", "b.com"]);
+
+        assert_eq!(values(&options, "--site"), Ok(vec!["a.com", "b.com"]));
+        assert_eq!(values(&options, "--app"), Ok(vec!["firefox"]));
+        assert_eq!(values(&options, "--goal"), Ok(vec![]));
+    }
+
+    #[test]
+    fn last_value_wins() {
+        let options = args(&["--duration", "10m", "--duration", "20m"]);
+
+        assert_eq!(value(&options, "--duration"), Ok(Some("20m")));
+        assert_eq!(value(&options, "--goal"), Ok(None));
+    }
+
+    #[test]
+    fn values_are_not_taken_for_options() {
+        // An option right after another is never read as its value
+        let options = args(&["--goal", "--site", "--strict"]);
+        assert!(value(&options, "--goal").is_err());
+
+        let options = args(&["--goal", "Write", "--site", "--app", "firefox"]);
+        assert_eq!(value(&options, "--goal"), Ok(Some("Write")));
+        assert!(values(&options, "--site").is_err());
+        assert_eq!(values(&options, "--app"), Ok(vec!["firefox"]));
+    }
+
+    #[test]
+    fn a_trailing_option_without_a_value_is_an_error() {
+        let options = args(&["--site", "a.com", "--site"]);
+
+        assert_eq!(
+            values(&options, "--site"),
+            Err("--site needs a value".to_string())
+        );
+    }
+
+    #[test]
+    fn flags_are_found_anywhere() {
+        let options = args(&["--goal", "Write", "--strict"]);
+
+        assert!(flag(&options, "--strict"));
+        assert!(!flag(&options, "--json"));
+    }
+}
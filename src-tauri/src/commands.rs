@@ -2,13 +2,32 @@ use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::sync::Mutex;
-use tauri::{AppHandle, Manager, PhysicalSize, State};
-
-use crate::block;
-use crate::app_blocker::{AppBlocker, BlockedApp, InstalledApp, search_installed_apps};
+use std::thread;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+use crate::block::{self, BlockReport};
+use crate::connections::{KILL_HELPER, KILL_HELPER_SCRIPT};
+use crate::dnd::Dnd;
+use crate::dns::FlushReport;
+use crate::blocklist::{BlocklistRegistry, Category, ImportReport};
+use crate::app_blocker::{AppBlocker, BlockedApp};
+use crate::app_index::{AppIndex, InstalledApp, ICON_SIZE};
+use crate::hooks::{Hook, HookStore};
+use crate::hosts_guard::{HostsGuard, TamperEvent};
+use crate::native_messaging::{self, BlockedVisit, BrowserBridge};
+use crate::notifications::{self, Kind, Notification};
+use crate::pomodoro::{self, Interval, PomodoroConfig, PomodoroState, PomodoroStatus};
+use crate::profiles::{EnforcementMode, Profile, ProfileInput, ProfileStore};
+use crate::reminders::ReminderSettings;
+use crate::schedule::{Schedule, ScheduleInput, Scheduler};
+use crate::session::{ActiveSession, Pause, SessionState};
+use crate::shortcuts::{self, ShortcutSettings, ShortcutStatus, ShortcutStore};
+use crate::windows::{self, WindowKind};
+use crate::xdg;
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Store {
@@ -17,43 +36,129 @@ pub struct Store {
     blocked_things: Vec<String>,
     #[serde(default)]
     blocked_apps: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    profile: Option<String>,
+    #[serde(default)]
+    enforcement: EnforcementMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    schedule: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pomodoro: Option<PomodoroConfig>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    intervals: Vec<Interval>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pauses: Vec<Pause>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tamper_events: Vec<TamperEvent>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Session {
-    goal: String,
-    duration: u64,
-    blocked_things: Vec<String>,
+    pub goal: String,
+    pub duration: u64,
+    pub blocked_things: Vec<String>,
     #[serde(default)]
-    blocked_apps: Vec<String>,
-    timestamp: i64,
+    pub blocked_apps: Vec<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub enforcement: EnforcementMode,
+    #[serde(default)]
+    pub schedule: Option<String>,
+    #[serde(default)]
+    pub pomodoro: Option<PomodoroConfig>,
+    #[serde(default)]
+    pub intervals: Vec<Interval>,
+    #[serde(default)]
+    pub pauses: Vec<Pause>,
+    /// Seconds spent paused, which do not count as focus time.
+    #[serde(default)]
+    pub paused_duration: u64,
+    #[serde(default)]
+    pub tamper_events: Vec<TamperEvent>,
+    pub timestamp: i64,
+}
+
+/// Everything needed to start a session, whether it was configured by hand,
+/// comes from a profile or was triggered by a schedule.
+pub struct SessionRequest {
+    pub goal: String,
+    pub duration: u64,
+    pub blocked_things: Vec<String>,
+    pub blocked_apps: Vec<String>,
+    pub profile: Option<String>,
+    pub enforcement: EnforcementMode,
+    pub schedule: Option<String>,
+    pub pomodoro: Option<PomodoroConfig>,
+    pub reminders: ReminderSettings,
+}
+
+impl SessionRequest {
+    pub fn from_profile(profile: Profile) -> Self {
+        Self {
+            goal: profile.name,
+            duration: profile.duration,
+            blocked_things: profile.site_rules,
+            blocked_apps: profile.app_rules,
+            profile: Some(profile.id),
+            enforcement: profile.enforcement,
+            schedule: None,
+            pomodoro: None,
+            reminders: profile.reminders,
+        }
+    }
 }
 
 type Result<T> = std::result::Result<T, String>;
 const STORAGE_DIR: &str = "/home/dipxsy/.focus_sessions";
 
-#[tauri::command]
-pub fn setup_persistent_authorization() -> Result<String> {
-    const SUDOERS_FILE: &str = "/etc/sudoers.d/focus";
+/// Total time a session can spend paused, in seconds.
+const PAUSE_BUDGET: u64 = 10 * 60;
+const PAUSE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const DEADLINE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const SUDOERS_FILE: &str = "/etc/sudoers.d/focus";
 
-    if Path::new(SUDOERS_FILE).exists() {
-        return Ok("Authorization already configured".to_string());
-    }
+/// A copy of the rules last installed, since the sudoers file itself is
+/// only readable by root.
+fn installed_sudoers_path() -> PathBuf {
+    xdg::app_data_dir().join("sudoers")
+}
 
+fn sudoers_content() -> Result<String> {
     let username = std::env::var("USER")
         .or_else(|_| std::env::var("USERNAME"))
         .map_err(|_| "Failed to get username".to_string())?;
 
-    let sudoers_content = format!(
+    Ok(format!(
         "# Focus App - Passwordless sudo for specific commands\n\
          {} ALL=(ALL) NOPASSWD: /usr/bin/tee /etc/hosts\n\
          {} ALL=(ALL) NOPASSWD: /usr/bin/cp * /etc/hosts\n\
-         {} ALL=(ALL) NOPASSWD: /usr/bin/systemctl restart systemd-resolved\n\
-         {} ALL=(ALL) NOPASSWD: /usr/bin/systemctl restart nscd\n\
          {} ALL=(ALL) NOPASSWD: /usr/bin/resolvectl flush-caches\n\
-         {} ALL=(ALL) NOPASSWD: /usr/bin/systemd-resolve --flush-caches\n",
-        username, username, username, username, username, username
-    );
+         {} ALL=(ALL) NOPASSWD: /usr/bin/systemd-resolve --flush-caches\n\
+         {} ALL=(ALL) NOPASSWD: /usr/sbin/nscd -i hosts\n\
+         {} ALL=(ALL) NOPASSWD: /usr/bin/pkill -HUP -x dnsmasq\n\
+         {} ALL=(ALL) NOPASSWD: {}\n",
+        username, username, username, username, username, username, username, KILL_HELPER
+    ))
+}
+
+/// Whether the sudoers file and the connection helper are installed as
+/// this version of Brisk needs them; an older install lacks the rules
+/// added since.
+fn authorization_current(content: &str) -> bool {
+    Path::new(SUDOERS_FILE).exists()
+        && fs::read_to_string(SUDOERS_FILE)
+            .or_else(|_| fs::read_to_string(installed_sudoers_path()))
+            .is_ok_and(|installed| installed == content)
+        && fs::read_to_string(KILL_HELPER).is_ok_and(|helper| helper == KILL_HELPER_SCRIPT)
+}
+
+#[tauri::command]
+pub fn setup_persistent_authorization() -> Result<String> {
+    let sudoers_content = sudoers_content()?;
+    if authorization_current(&sudoers_content) {
+        return Ok("Authorization already configured".to_string());
+    }
 
     let temp_file = std::env::temp_dir().join("focus_sudoers_temp");
     fs::write(&temp_file, &sudoers_content)
@@ -63,6 +168,14 @@ pub fn setup_persistent_authorization() -> Result<String> {
         .to_str()
         .ok_or("Failed to convert temp file path to string")?;
 
+    let helper_file = std::env::temp_dir().join("focus_kill_helper_temp");
+    fs::write(&helper_file, KILL_HELPER_SCRIPT)
+        .map_err(|e| format!("Failed to create temporary file: {}", e))?;
+
+    let helper_file_str = helper_file
+        .to_str()
+        .ok_or("Failed to convert temp file path to string")?;
+
 
     if !Command::new("which")
         .arg("pkexec")
@@ -70,6 +183,7 @@ pub fn setup_persistent_authorization() -> Result<String> {
         .is_ok_and(|o| o.status.success())
     {
         let _ = fs::remove_file(&temp_file);
+        let _ = fs::remove_file(&helper_file);
         return Err(
             "pkexec is not installed. Please install it:\n\n\
              Ubuntu/Debian: sudo apt install policykit-1\n\
@@ -83,10 +197,11 @@ pub fn setup_persistent_authorization() -> Result<String> {
     let script_path = std::env::temp_dir().join("focus_install_sudoers.sh");
     let script_content = format!(
         "#!/bin/bash\n\
+         install -D -m 755 -o root -g root {} {}\n\
          cp {} /etc/sudoers.d/focus\n\
          chmod 440 /etc/sudoers.d/focus\n\
          chown root:root /etc/sudoers.d/focus\n",
-        temp_file_str
+        helper_file_str, KILL_HELPER, temp_file_str
     );
     
     fs::write(&script_path, script_content)
@@ -115,6 +230,7 @@ pub fn setup_persistent_authorization() -> Result<String> {
 
     let _ = fs::remove_file(&script_path);
     let _ = fs::remove_file(&temp_file);
+    let _ = fs::remove_file(&helper_file);
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -132,19 +248,26 @@ pub fn setup_persistent_authorization() -> Result<String> {
         ));
     }
 
+    let record = installed_sudoers_path();
+    if let Some(dir) = record.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(e) = fs::write(&record, &sudoers_content) {
+        eprintln!("Failed to record installed sudoers rules: {}", e);
+    }
+
     Ok("Authorization configured successfully".to_string())
 }
 
 #[tauri::command]
 pub fn check_authorization_status() -> Result<bool> {
-    const SUDOERS_FILE: &str = "/etc/sudoers.d/focus";
-    Ok(Path::new(SUDOERS_FILE).exists())
+    Ok(authorization_current(&sudoers_content()?))
 }
 
 #[tauri::command]
-pub fn remove_authorization() -> Result<String> {
-    const SUDOERS_FILE: &str = "/etc/sudoers.d/focus";
-    
+pub fn remove_authorization(session_state: State<SessionState>) -> Result<String> {
+    session_state.ensure_unlocked(Utc::now().timestamp())?;
+
     if !Path::new(SUDOERS_FILE).exists() {
         return Ok("No authorization to remove".to_string());
     }
@@ -162,8 +285,7 @@ pub fn remove_authorization() -> Result<String> {
     }
 
     let output = Command::new("pkexec")
-        .arg("rm")
-        .arg(SUDOERS_FILE)
+        .args(["rm", "-f", SUDOERS_FILE, KILL_HELPER])
         .output()
         .map_err(|e| format!("Failed to remove authorization: {}", e))?;
 
@@ -171,6 +293,7 @@ pub fn remove_authorization() -> Result<String> {
         let stderr = String::from_utf8_lossy(&output.stderr);
         return Err(format!("Failed to remove authorization: {}", stderr));
     }
+    let _ = fs::remove_file(installed_sudoers_path());
 
     Ok("Authorization removed successfully".to_string())
 }
@@ -181,33 +304,110 @@ pub fn create_and_store_session(
     duration: u64,
     blocked_things: Vec<String>,
     blocked_apps: Vec<String>,
-    app_blocker: State<Mutex<AppBlocker>>,
+    app: AppHandle,
 ) -> Result<String> {
-    if !blocked_things.is_empty() {
-        block::block_sites(&blocked_things).map_err(|e| format!("Failed to block sites: {}", e))?;
+    start_session(
+        &app,
+        SessionRequest {
+            goal,
+            duration,
+            blocked_things,
+            blocked_apps,
+            profile: None,
+            enforcement: EnforcementMode::default(),
+            schedule: None,
+            pomodoro: None,
+            reminders: ReminderSettings::default(),
+        },
+    )
+}
+
+#[tauri::command]
+pub fn start_session_from_profile(
+    id: String,
+    goal: Option<String>,
+    duration: Option<u64>,
+    app: AppHandle,
+) -> Result<String> {
+    let profile = app
+        .state::<ProfileStore>()
+        .get(&id)
+        .ok_or_else(|| format!("No profile with id {}", id))?;
+
+    let mut request = SessionRequest::from_profile(profile);
+    if let Some(goal) = goal.filter(|g| !g.trim().is_empty()) {
+        request.goal = goal;
     }
-    
-    // Start app blocking if apps are specified
-    if !blocked_apps.is_empty() {
-        let apps: Vec<BlockedApp> = blocked_apps.iter().map(|app_json| {
-            // Parse app JSON string (format: "name|||executable|||icon")
-            let parts: Vec<&str> = app_json.split("|||").collect();
-            BlockedApp {
-                name: parts.get(0).unwrap_or(&"Unknown").to_string(),
-                executable: parts.get(1).unwrap_or(&"unknown").to_string(),
-                icon: parts.get(2).map(|s| s.to_string()),
-            }
-        }).collect();
-        
-        let blocker = app_blocker.lock().unwrap();
-        blocker.start_blocking(apps).map_err(|e| format!("Failed to start app blocking: {}", e))?;
+    if let Some(duration) = duration {
+        if duration == 0 {
+            return Err("Duration must be greater than zero".to_string());
+        }
+        request.duration = duration;
     }
-    
-    let store = Store {
+
+    start_session(&app, request)
+}
+
+/// Blocks the requested sites and apps and records the session.
+pub fn start_session(app: &AppHandle, request: SessionRequest) -> Result<String> {
+    // Starting over a running session would let a strict one be replaced by
+    // one that can be ended, and would leave its record unfinished
+    if app.state::<SessionState>().current().is_some() {
+        return Err("A session is already running".to_string());
+    }
+
+    let started_at = Utc::now().timestamp();
+    let ends_at = started_at + request.duration as i64;
+    let block_report = apply_blocking(app, &request.blocked_things, &request.blocked_apps, ends_at)
+        .inspect_err(|_| abandon_blocking(app))?;
+
+    let active = record_session(request, started_at).inspect_err(|_| abandon_blocking(app))?;
+    if let Err(e) = app.state::<SessionState>().begin(active.clone()) {
+        let _ = fs::remove_file(&active.record);
+        abandon_blocking(app);
+        return Err(e);
+    }
+    let app_index = app.state::<AppIndex>();
+    for blocked in &active.blocked_apps {
+        app_index.record_usage(&BlockedApp::from_encoded(blocked).executable);
+    }
+    let _ = app.emit("session-started", &active);
+
+    println!("New session stored at {:?}", active.record);
+    Ok(format!(
+        "Session created successfully at {:?}. {}",
+        active.record,
+        block_report.summary()
+    ))
+}
+
+/// Writes the history record of a session starting at `started_at` and
+/// returns it as the session to keep active.
+pub fn record_session(request: SessionRequest, started_at: i64) -> Result<ActiveSession> {
+    let SessionRequest {
         goal,
         duration,
         blocked_things,
         blocked_apps,
+        profile,
+        enforcement,
+        schedule,
+        pomodoro,
+        reminders,
+    } = request;
+
+    let store = Store {
+        goal: goal.clone(),
+        duration,
+        blocked_things: blocked_things.clone(),
+        blocked_apps: blocked_apps.clone(),
+        profile: profile.clone(),
+        enforcement,
+        schedule: schedule.clone(),
+        pomodoro,
+        intervals: Vec::new(),
+        pauses: Vec::new(),
+        tamper_events: Vec::new(),
     };
 
     let dir = Path::new(STORAGE_DIR);
@@ -216,7 +416,7 @@ pub fn create_and_store_session(
         fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
     }
 
-    let file_name = format!("session_{}.json", Utc::now().timestamp());
+    let file_name = format!("session_{}.json", started_at);
     let file_path = dir.join(file_name);
 
     let json_data =
@@ -228,19 +428,372 @@ pub fn create_and_store_session(
     file.write_all(json_data.as_bytes())
         .map_err(|e| format!("Failed to write: {}", e))?;
 
-    println!("New session stored at {:?}", file_path);
-    Ok(format!("Session created successfully at {:?}", file_path))
+    Ok(ActiveSession {
+        goal,
+        started_at,
+        ends_at: started_at + duration as i64,
+        profile,
+        enforcement,
+        schedule,
+        record: file_path,
+        blocked_things,
+        blocked_apps,
+        pause: None,
+        paused_for: 0,
+        pause_budget: PAUSE_BUDGET,
+        unlocked: false,
+        reminders,
+    })
+}
+
+/// The Pomodoro configuration of a session and the intervals it finished
+/// so far, or `None` for a session that is not a Pomodoro cycle.
+pub fn recorded_pomodoro(session: &ActiveSession) -> Option<(PomodoroConfig, Vec<Interval>)> {
+    let store = read_record(session).ok()?;
+    Some((store.pomodoro?, store.intervals))
+}
+
+/// Appends a finished Pomodoro interval to the active session's record.
+pub fn record_interval(app: &AppHandle, interval: Interval) -> Result<()> {
+    let session = app
+        .state::<SessionState>()
+        .current()
+        .ok_or("No active session")?;
+
+    update_record(&session, |store| store.intervals.push(interval))
+}
+
+/// Records hosts file tampering detected during the active session.
+pub fn record_tamper_event(app: &AppHandle, event: TamperEvent) -> Result<()> {
+    let session = app
+        .state::<SessionState>()
+        .current()
+        .ok_or("No active session")?;
+
+    update_record(&session, |store| store.tamper_events.push(event))
+}
+
+fn read_record(session: &ActiveSession) -> Result<Store> {
+    let content = fs::read_to_string(&session.record)
+        .map_err(|e| format!("Failed to read session: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse session: {}", e))
+}
+
+fn update_record(session: &ActiveSession, update: impl FnOnce(&mut Store)) -> Result<()> {
+    let mut store = read_record(session)?;
+    update(&mut store);
+
+    let json_data =
+        serde_json::to_string_pretty(&store).map_err(|e| format!("Failed to serialize {}", e))?;
+    fs::write(&session.record, json_data).map_err(|e| format!("Failed to write: {}", e))
+}
+
+/// Lifts blocking for up to `duration` seconds, or whatever is left of the
+/// session's pause budget, after which blocking resumes on its own.
+#[tauri::command]
+pub fn pause_session(duration: Option<u64>, app: AppHandle) -> Result<ActiveSession> {
+    let session = app
+        .state::<SessionState>()
+        .current()
+        .ok_or("No active session")?;
+
+    if app.state::<PomodoroState>().current().is_some() {
+        return Err("Pomodoro sessions cannot be paused outside their breaks".to_string());
+    }
+    if session.is_locked(Utc::now().timestamp()) {
+        return Err("Strict sessions cannot be paused".to_string());
+    }
+
+    let remaining = session.remaining_pause_budget();
+    if remaining == 0 {
+        return Err("The pause budget for this session is used up".to_string());
+    }
+
+    let now = Utc::now().timestamp();
+    let length = duration.unwrap_or(remaining).min(remaining);
+    if length == 0 {
+        return Err("Pause length must be greater than zero".to_string());
+    }
+
+    let paused = app
+        .state::<SessionState>()
+        .pause(now, now + length as i64)?;
+    if let Err(e) = lift_blocking(&app) {
+        // Leave the session blocked rather than paused with blocking still on
+        let _ = app.state::<SessionState>().resume(now);
+        return Err(e);
+    }
+    let _ = app.emit("session-paused", &paused);
+
+    thread::spawn(move || auto_resume(&app, now));
+
+    Ok(paused)
+}
+
+#[tauri::command]
+pub fn resume_session(app: AppHandle) -> Result<ActiveSession> {
+    resume(&app)
+}
+
+/// Re-applies blocking after a pause and records how long it lasted.
+pub fn resume(app: &AppHandle) -> Result<ActiveSession> {
+    let paused = app
+        .state::<SessionState>()
+        .current()
+        .ok_or("No active session")?;
+    if paused.pause.is_none() {
+        return Err("The session is not paused".to_string());
+    }
+
+    // Blocking goes back on before the pause is cleared, so the session is
+    // never seen running without it, and a failure leaves it paused
+    apply_blocking(
+        app,
+        &paused.blocked_things,
+        &paused.blocked_apps,
+        paused.ends_at,
+    )?;
+    let (session, pause) = app.state::<SessionState>().resume(Utc::now().timestamp())?;
+
+    if let Err(e) = update_record(&session, |store| store.pauses.push(pause)) {
+        eprintln!("Failed to record pause: {}", e);
+    }
+    let _ = app.emit("session-resumed", &session);
+
+    Ok(session)
+}
+
+/// Resumes the pause that started at `paused_at` once it runs out, unless
+/// it was resumed or the session ended in the meantime.
+fn auto_resume(app: &AppHandle, paused_at: i64) {
+    loop {
+        let Some(pause) = app
+            .state::<SessionState>()
+            .current()
+            .and_then(|s| s.pause)
+            .filter(|p| p.started_at == paused_at)
+        else {
+            return;
+        };
+
+        if Utc::now().timestamp() >= pause.resumes_at {
+            match resume(app) {
+                Ok(_) => return,
+                // Still paused, so the next check tries again
+                Err(e) => eprintln!("Failed to resume session after pause: {}", e),
+            }
+        }
+        thread::sleep(PAUSE_CHECK_INTERVAL);
+    }
+}
+
+/// Ends the active session once its deadline passes, so blocking never
+/// depends on a window being open to count down. Pomodoro cycles end
+/// themselves after their last phase.
+pub fn watch_deadline(app: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(DEADLINE_CHECK_INTERVAL);
+
+        let Some(session) = app.state::<SessionState>().current() else {
+            continue;
+        };
+        if Utc::now().timestamp() < session.ends_at
+            || app.state::<PomodoroState>().current().is_some()
+        {
+            continue;
+        }
+
+        match end_session(&app) {
+            Ok(report) => {
+                println!("Session {} complete. {}", session.goal, report.summary());
+                notify_session_complete((session.ends_at - session.started_at) as u64 / 60);
+            }
+            // Still active, so the next check tries again
+            Err(e) => eprintln!("Failed to end session at its deadline: {}", e),
+        }
+    });
+}
+
+#[tauri::command]
+pub fn start_pomodoro(
+    goal: String,
+    blocked_things: Vec<String>,
+    blocked_apps: Vec<String>,
+    config: PomodoroConfig,
+    app: AppHandle,
+) -> Result<String> {
+    pomodoro::start(
+        app,
+        SessionRequest {
+            goal,
+            duration: config.total_duration(),
+            blocked_things,
+            blocked_apps,
+            profile: None,
+            enforcement: EnforcementMode::default(),
+            schedule: None,
+            pomodoro: Some(config),
+            reminders: ReminderSettings::default(),
+        },
+    )
+}
+
+#[tauri::command]
+pub fn get_pomodoro_status(pomodoro: State<PomodoroState>) -> Result<Option<PomodoroStatus>> {
+    Ok(pomodoro.current())
+}
+
+#[tauri::command]
+pub fn unblock_all_sites(app: AppHandle) -> Result<FlushReport> {
+    app.state::<SessionState>()
+        .ensure_unlocked(Utc::now().timestamp())?;
+    end_session(&app)
+}
+
+/// Returns the text to type back to `complete_unlock_challenge` to end a
+/// strict session early.
+#[tauri::command]
+pub fn request_unlock_challenge(session_state: State<SessionState>) -> Result<String> {
+    session_state.new_challenge(Utc::now().timestamp())
 }
 
 #[tauri::command]
-pub fn unblock_all_sites(app_blocker: State<Mutex<AppBlocker>>) -> Result<String> {
-    block::unblock_sites().map_err(|e| format!("Failed to unblock sites: {}", e))?;
+pub fn complete_unlock_challenge(response: String, app: AppHandle) -> Result<ActiveSession> {
+    let session = app.state::<SessionState>().complete_challenge(&response)?;
+    let _ = app.emit("session-unlocked", &session);
+    Ok(session)
+}
+
+/// Picks up a session that was running when Brisk last exited: ends it if
+/// its deadline has passed, otherwise continues its Pomodoro cycle or
+/// re-applies its blocking.
+pub fn restore_session(app: &AppHandle) {
+    let Some(session) = app.state::<SessionState>().current() else {
+        return;
+    };
+
+    let now = Utc::now().timestamp();
+    if now >= session.ends_at {
+        if let Err(e) = end_session(app) {
+            eprintln!("Failed to end expired session: {}", e);
+        }
+        return;
+    }
+
+    if pomodoro::restore(app, &session) {
+        return;
+    }
+
+    if let Some(pause) = session.pause {
+        let app = app.clone();
+        thread::spawn(move || auto_resume(&app, pause.started_at));
+        return;
+    }
+
+    if let Err(e) = apply_blocking(
+        app,
+        &session.blocked_things,
+        &session.blocked_apps,
+        session.ends_at,
+    ) {
+        eprintln!("Failed to restore blocking for {}: {}", session.goal, e);
+    }
+}
+
+/// Lifts all site and app blocking and forgets the active session.
+pub fn end_session(app: &AppHandle) -> Result<FlushReport> {
+    let dns_report = lift_blocking(app)?;
+
+    if let Some(session) = finish_session(&app.state::<SessionState>()) {
+        let _ = app.emit("session-ended", &session);
+    }
+
+    Ok(dns_report)
+}
+
+/// Forgets the active session, first recording a pause that is still
+/// running.
+pub fn finish_session(session_state: &SessionState) -> Option<ActiveSession> {
+    if session_state.current().is_some_and(|s| s.pause.is_some()) {
+        if let Ok((session, pause)) = session_state.resume(Utc::now().timestamp()) {
+            if let Err(e) = update_record(&session, |store| store.pauses.push(pause)) {
+                eprintln!("Failed to record pause: {}", e);
+            }
+        }
+    }
+
+    session_state.finish()
+}
+
+/// Blocks sites and apps without touching the session record, so blocking
+/// can be re-applied after a break.
+pub fn apply_blocking(
+    app: &AppHandle,
+    blocked_things: &[String],
+    blocked_apps: &[String],
+    ends_at: i64,
+) -> Result<BlockReport> {
+    let sites = app.state::<BlocklistRegistry>().expand(blocked_things);
+
+    let block_report = if !sites.is_empty() {
+        block::block_sites(&sites).map_err(|e| format!("Failed to block sites: {}", e))?
+    } else {
+        BlockReport::default()
+    };
+    app.state::<HostsGuard>().expect(app, &sites);
+
+    app.state::<BrowserBridge>().set_session(&sites, Some(ends_at));
+    
+    // Start app blocking if apps are specified
+    if !blocked_apps.is_empty() {
+        let apps: Vec<BlockedApp> = blocked_apps
+            .iter()
+            .map(|app| BlockedApp::from_encoded(app))
+            .collect();
+
+        let blocker = app.state::<Mutex<AppBlocker>>();
+        let blocker = blocker.lock().unwrap();
+        blocker.start_blocking(apps).map_err(|e| format!("Failed to start app blocking: {}", e))?;
+    }
+
+    // Notifications from other apps are a distraction too, but failing to
+    // silence them is no reason to give up on the session
+    if let Err(e) = app.state::<Dnd>().enable() {
+        eprintln!("Failed to turn on Do-Not-Disturb: {}", e);
+    }
+
+    Ok(block_report)
+}
+
+/// Undoes `apply_blocking`, in part or in full, for a session that failed
+/// to start, so nothing stays blocked without a session to end it.
+fn abandon_blocking(app: &AppHandle) {
+    if let Err(e) = lift_blocking(app) {
+        eprintln!(
+            "Failed to lift blocking of a session that did not start: {}",
+            e
+        );
+    }
+}
+
+/// Lifts site and app blocking while leaving the session itself running.
+pub fn lift_blocking(app: &AppHandle) -> Result<FlushReport> {
+    let dns_report = app
+        .state::<HostsGuard>()
+        .lift(block::unblock_sites)
+        .map_err(|e| format!("Failed to unblock sites: {}", e))?;
+    app.state::<BrowserBridge>().clear_session();
     
     // Stop app blocking
-    let blocker = app_blocker.lock().unwrap();
+    let blocker = app.state::<Mutex<AppBlocker>>();
+    let blocker = blocker.lock().unwrap();
     blocker.stop_blocking().map_err(|e| format!("Failed to stop app blocking: {}", e))?;
-    
-    Ok("Sites and apps unblocked successfully".to_string())
+
+    if let Err(e) = app.state::<Dnd>().restore() {
+        eprintln!("Failed to restore notification settings: {}", e);
+    }
+
+    Ok(dns_report)
 }
 
 #[tauri::command]
@@ -280,6 +833,18 @@ pub fn get_all_sessions() -> Result<Vec<Session>> {
                                     duration: store.duration,
                                     blocked_things: store.blocked_things,
                                     blocked_apps: store.blocked_apps,
+                                    profile: store.profile,
+                                    enforcement: store.enforcement,
+                                    schedule: store.schedule,
+                                    pomodoro: store.pomodoro,
+                                    intervals: store.intervals,
+                                    paused_duration: store
+                                        .pauses
+                                        .iter()
+                                        .map(|p| (p.ended_at - p.started_at).max(0) as u64)
+                                        .sum(),
+                                    pauses: store.pauses,
+                                    tamper_events: store.tamper_events,
                                     timestamp,
                                 });
                             }
@@ -296,198 +861,204 @@ pub fn get_all_sessions() -> Result<Vec<Session>> {
     Ok(sessions)
 }
 
-const MAIN_WINDOW_WIDTH: u32 = 1600;
-const MAIN_WINDOW_HEIGHT: u32 = 1200;
-const MAIN_WINDOW_MIN_WIDTH: u32 = 900;
-const MAIN_WINDOW_MIN_HEIGHT: u32 = 600;
-
-const STATS_WINDOW_WIDTH: u32 = 1600;
-const STATS_WINDOW_HEIGHT: u32 = 1200;
-
-const WIDGET_WINDOW_WIDTH: u32 = 400;
-const WIDGET_WINDOW_HEIGHT: u32 = 80;
-
-#[tauri::command]
-pub async fn resize_window_to_widget(app: AppHandle) -> Result<String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
-
-    let _ = window.unmaximize();
-    let _ = window.set_fullscreen(false);
-
-    window
-        .set_size(PhysicalSize::new(WIDGET_WINDOW_WIDTH, WIDGET_WINDOW_HEIGHT))
-        .map_err(|e| format!("Failed to set window size: {}", e))?;
-
-    window
-        .set_min_size(Some(PhysicalSize::new(
-            WIDGET_WINDOW_WIDTH,
-            WIDGET_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set min size: {}", e))?;
-
-    window
-        .set_max_size(Some(PhysicalSize::new(
-            WIDGET_WINDOW_WIDTH,
-            WIDGET_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set max size: {}", e))?;
-
-    window
-        .set_always_on_top(true)
-        .map_err(|e| format!("Failed to set always on top: {}", e))?;
-
-    Ok("Window resized to widget size".to_string())
-}
-
-#[tauri::command]
-pub async fn resize_window_to_main(app: AppHandle) -> Result<String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
-
-    // Ensure window is not maximized or fullscreen first
-    let _ = window.set_fullscreen(false);
-    let _ = window.unmaximize();
-    let _ = window.set_always_on_top(false);
-
-    // Ensure window is visible and focused
-    let _ = window.show();
-    let _ = window.set_focus();
-
-    // First, ensure window is resizable
-    window
-        .set_resizable(true)
-        .map_err(|e| format!("Failed to set window resizable: {}", e))?;
-
-    // Step 1: Clear ALL constraints first to remove stats/widget mode restrictions
-    window
-        .set_max_size(None::<PhysicalSize<u32>>)
-        .map_err(|e| format!("Failed to remove max size: {}", e))?;
-
-    window
-        .set_min_size(None::<PhysicalSize<u32>>)
-        .map_err(|e| format!("Failed to clear min size: {}", e))?;
-
-    // Step 2: Set both min and max to 900x600 to lock the window at that size
-    window
-        .set_min_size(Some(PhysicalSize::new(
-            MAIN_WINDOW_WIDTH,
-            MAIN_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set min size: {}", e))?;
-
-    window
-        .set_max_size(Some(PhysicalSize::new(
-            MAIN_WINDOW_WIDTH,
-            MAIN_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set max size: {}", e))?;
-
-    // Step 3: Set the window size to main dimensions
-    window
-        .set_size(PhysicalSize::new(MAIN_WINDOW_WIDTH, MAIN_WINDOW_HEIGHT))
-        .map_err(|e| format!("Failed to set window size: {}", e))?;
-
-    // Verify the size was set correctly, retry if needed
-    let current_size = window
-        .inner_size()
-        .map_err(|e| format!("Failed to get current size: {}", e))?;
-
-    if current_size.width != MAIN_WINDOW_WIDTH || current_size.height != MAIN_WINDOW_HEIGHT {
-        println!(
-            "Warning: Window size is {}x{}, retrying...",
-            current_size.width, current_size.height
-        );
+/// Executables blocked in stored sessions, used to rank app search results.
+pub fn blocked_app_history() -> Vec<String> {
+    get_all_sessions()
+        .unwrap_or_default()
+        .into_iter()
+        .flat_map(|session| session.blocked_apps)
+        .map(|app| BlockedApp::from_encoded(&app).executable)
+        .collect()
+}
+
+/// Shows one of the app's windows, creating it if needed.
+#[tauri::command]
+pub async fn show_window(window: WindowKind, app: AppHandle) -> Result<String> {
+    windows::open(&app, window)?;
+    Ok("Window shown".to_string())
+}
 
-        // Try again with more aggressive approach
-        window
-            .set_size(PhysicalSize::new(MAIN_WINDOW_WIDTH, MAIN_WINDOW_HEIGHT))
-            .map_err(|e| format!("Failed to retry window size: {}", e))?;
+#[tauri::command]
+pub async fn close_window(window: WindowKind, app: AppHandle) -> Result<String> {
+    windows::close(&app, window)?;
+    Ok("Window closed".to_string())
+}
+
+#[tauri::command]
+pub fn search_apps(query: String, app_index: State<AppIndex>) -> Result<Vec<InstalledApp>> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
     }
 
-    let _ = window.center();
+    Ok(app_index.search(&query, 10))
+}
 
-    let final_size = window
-        .inner_size()
-        .map_err(|e| format!("Failed to get final size: {}", e))?;
-    println!(
-        "Window resized to main size: {}x{} (requested: {}x{})",
-        final_size.width, final_size.height, MAIN_WINDOW_WIDTH, MAIN_WINDOW_HEIGHT
-    );
+#[tauri::command]
+pub fn resolve_icon(
+    icon: String,
+    size: Option<u32>,
+    app_index: State<AppIndex>,
+) -> Result<Option<String>> {
+    Ok(app_index.resolve_icon(&icon, size.unwrap_or(ICON_SIZE)))
+}
 
-    Ok(format!(
-        "Window resized to main size: {}x{}",
-        final_size.width, final_size.height
-    ))
+#[tauri::command]
+pub fn list_blocklists(blocklists: State<BlocklistRegistry>) -> Result<Vec<Category>> {
+    Ok(blocklists.list())
 }
 
 #[tauri::command]
-pub async fn resize_window_to_stats(app: AppHandle) -> Result<String> {
-    let window = app
-        .get_webview_window("main")
-        .ok_or("Main window not found")?;
-    
-    let _ = window.set_fullscreen(false);
-    let _ = window.unmaximize();
-    let _ = window.set_always_on_top(false);
-    let _ = window.show();
-    let _ = window.set_focus();
-    
-    
-    window
-        .set_resizable(true)
-        .map_err(|e| format!("Failed to set window resizable: {}", e))?;
-    window
-        .set_max_size(None::<PhysicalSize<u32>>)
-        .map_err(|e| format!("Failed to remove max size: {}", e))?;
-
-    window
-        .set_min_size(None::<PhysicalSize<u32>>)
-        .map_err(|e| format!("Failed to clear min size: {}", e))?;
-
-    // Step 2: Set both min and max to 900x600 to lock the window at that size
-    window
-        .set_min_size(Some(PhysicalSize::new(
-            MAIN_WINDOW_WIDTH,
-            MAIN_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set min size: {}", e))?;
-
-    window
-        .set_max_size(Some(PhysicalSize::new(
-            STATS_WINDOW_WIDTH,
-            STATS_WINDOW_HEIGHT,
-        )))
-        .map_err(|e| format!("Failed to set max size: {}", e))?;
-    window
-        .set_size(PhysicalSize::new(STATS_WINDOW_WIDTH, STATS_WINDOW_HEIGHT))
-        .map_err(|e| format!("Failed to set window size: {}", e))?;
-
-    let _ = window.center();
-
-    let current_size = window
-        .inner_size()
-        .map_err(|e| format!("Failed to get current size: {}", e))?;
-    println!(
-        "Window resized to stats size: {}x{} (requested: {}x{})",
-        current_size.width, current_size.height, STATS_WINDOW_WIDTH, STATS_WINDOW_HEIGHT
-    );
+pub fn create_blocklist(
+    name: String,
+    domains: Vec<String>,
+    blocklists: State<BlocklistRegistry>,
+) -> Result<Category> {
+    blocklists.create(&name, &domains)
+}
 
-    Ok(format!(
-        "Window resized to stats size: {}x{}",
-        current_size.width, current_size.height
-    ))
+#[tauri::command]
+pub fn update_blocklist(
+    id: String,
+    name: String,
+    domains: Vec<String>,
+    blocklists: State<BlocklistRegistry>,
+) -> Result<Category> {
+    blocklists.update(&id, &name, &domains)
 }
 
 #[tauri::command]
-pub fn search_apps(query: String) -> Result<Vec<InstalledApp>> {
-    if query.trim().is_empty() {
-        return Ok(Vec::new());
+pub fn delete_blocklist(id: String, blocklists: State<BlocklistRegistry>) -> Result<String> {
+    blocklists.delete(&id)?;
+    Ok("Blocklist deleted".to_string())
+}
+
+#[tauri::command]
+pub fn import_blocklist(
+    path: String,
+    name: Option<String>,
+    blocklists: State<BlocklistRegistry>,
+) -> Result<ImportReport> {
+    blocklists.import(Path::new(&path), name.as_deref())
+}
+
+#[tauri::command]
+pub fn list_profiles(profiles: State<ProfileStore>) -> Result<Vec<Profile>> {
+    Ok(profiles.list())
+}
+
+#[tauri::command]
+pub fn create_profile(profile: ProfileInput, profiles: State<ProfileStore>) -> Result<Profile> {
+    profiles.create(profile)
+}
+
+#[tauri::command]
+pub fn update_profile(
+    id: String,
+    profile: ProfileInput,
+    profiles: State<ProfileStore>,
+) -> Result<Profile> {
+    profiles.update(&id, profile)
+}
+
+/// Deletes a profile no schedule starts, and stops the default-profile
+/// shortcut from pointing at it.
+#[tauri::command]
+pub fn delete_profile(
+    id: String,
+    profiles: State<ProfileStore>,
+    scheduler: State<Scheduler>,
+    shortcut_store: State<ShortcutStore>,
+) -> Result<String> {
+    let schedules: Vec<String> = scheduler
+        .list()
+        .into_iter()
+        .filter(|schedule| schedule.profile == id)
+        .map(|schedule| schedule.name)
+        .collect();
+    if !schedules.is_empty() {
+        return Err(format!(
+            "Profile is used by {}; change or delete those schedules first",
+            schedules.join(", ")
+        ));
     }
-    
-    search_installed_apps(&query)
+
+    profiles.delete(&id)?;
+    if let Err(e) = shortcut_store.forget_profile(&id) {
+        eprintln!("Failed to clear the default profile: {}", e);
+    }
+    Ok("Profile deleted".to_string())
+}
+
+#[tauri::command]
+pub fn get_active_session(session_state: State<SessionState>) -> Result<Option<ActiveSession>> {
+    Ok(session_state.current())
+}
+
+#[tauri::command]
+pub fn list_schedules(scheduler: State<Scheduler>) -> Result<Vec<Schedule>> {
+    Ok(scheduler.list())
+}
+
+#[tauri::command]
+pub fn create_schedule(
+    schedule: ScheduleInput,
+    scheduler: State<Scheduler>,
+    profiles: State<ProfileStore>,
+) -> Result<Schedule> {
+    if profiles.get(&schedule.profile).is_none() {
+        return Err(format!("No profile with id {}", schedule.profile));
+    }
+    scheduler.create(schedule)
+}
+
+#[tauri::command]
+pub fn update_schedule(
+    id: String,
+    schedule: ScheduleInput,
+    scheduler: State<Scheduler>,
+    profiles: State<ProfileStore>,
+) -> Result<Schedule> {
+    if profiles.get(&schedule.profile).is_none() {
+        return Err(format!("No profile with id {}", schedule.profile));
+    }
+    scheduler.update(&id, schedule)
+}
+
+#[tauri::command]
+pub fn delete_schedule(id: String, scheduler: State<Scheduler>) -> Result<String> {
+    scheduler.delete(&id)?;
+    Ok("Schedule deleted".to_string())
+}
+
+#[tauri::command]
+pub fn list_hooks(hook_store: State<HookStore>) -> Result<Vec<Hook>> {
+    Ok(hook_store.list())
+}
+
+#[tauri::command]
+pub fn update_hooks(hooks: Vec<Hook>, hook_store: State<HookStore>) -> Result<Vec<Hook>> {
+    hook_store.set(hooks)
+}
+
+#[tauri::command]
+pub fn get_shortcuts(shortcut_store: State<ShortcutStore>) -> Result<ShortcutStatus> {
+    Ok(shortcut_store.status())
+}
+
+/// Saves and registers new global shortcuts. Bindings other applications
+/// already hold are returned as conflicts rather than failing the update.
+#[tauri::command]
+pub fn update_shortcuts(
+    settings: ShortcutSettings,
+    app: AppHandle,
+    profiles: State<ProfileStore>,
+) -> Result<ShortcutStatus> {
+    if let Some(id) = &settings.default_profile {
+        if profiles.get(id).is_none() {
+            return Err(format!("No profile with id {}", id));
+        }
+    }
+    shortcuts::update(&app, settings)
 }
 
 #[tauri::command]
@@ -495,14 +1066,10 @@ pub fn start_app_blocking(
     apps: Vec<String>,
     app_blocker: State<Mutex<AppBlocker>>,
 ) -> Result<String> {
-    let blocked_apps: Vec<BlockedApp> = apps.iter().map(|app_json| {
-        let parts: Vec<&str> = app_json.split("|||").collect();
-        BlockedApp {
-            name: parts.get(0).unwrap_or(&"Unknown").to_string(),
-            executable: parts.get(1).unwrap_or(&"unknown").to_string(),
-            icon: parts.get(2).map(|s| s.to_string()),
-        }
-    }).collect();
+    let blocked_apps: Vec<BlockedApp> = apps
+        .iter()
+        .map(|app| BlockedApp::from_encoded(app))
+        .collect();
     
     let blocker = app_blocker.lock().unwrap();
     blocker.start_blocking(blocked_apps)?;
@@ -511,7 +1078,12 @@ pub fn start_app_blocking(
 }
 
 #[tauri::command]
-pub fn stop_app_blocking(app_blocker: State<Mutex<AppBlocker>>) -> Result<String> {
+pub fn stop_app_blocking(
+    app_blocker: State<Mutex<AppBlocker>>,
+    session_state: State<SessionState>,
+) -> Result<String> {
+    session_state.ensure_unlocked(Utc::now().timestamp())?;
+
     let blocker = app_blocker.lock().unwrap();
     blocker.stop_blocking()?;
     Ok("App blocking stopped".to_string())
@@ -524,36 +1096,37 @@ pub fn get_block_attempts(app_blocker: State<Mutex<AppBlocker>>) -> Result<std::
 }
 
 #[tauri::command]
-pub fn show_session_complete_notification(duration_minutes: u64) -> Result<String> {
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Stdio;
-        
-        let duration_text = if duration_minutes >= 60 {
-            let hours = duration_minutes / 60;
-            let mins = duration_minutes % 60;
-            if mins > 0 {
-                format!("{} hour{} and {} minute{}", hours, if hours > 1 { "s" } else { "" }, mins, if mins > 1 { "s" } else { "" })
-            } else {
-                format!("{} hour{}", hours, if hours > 1 { "s" } else { "" })
-            }
+pub fn get_blocked_visits(browser_bridge: State<BrowserBridge>) -> Result<Vec<BlockedVisit>> {
+    Ok(browser_bridge.blocked_visits())
+}
+
+#[tauri::command]
+pub fn install_browser_integration(chrome_extension_ids: Vec<String>) -> Result<Vec<String>> {
+    let written = native_messaging::install_manifests(&chrome_extension_ids)?;
+    Ok(written
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect())
+}
+
+fn notify_session_complete(duration_minutes: u64) {
+    let duration_text = if duration_minutes >= 60 {
+        let hours = duration_minutes / 60;
+        let mins = duration_minutes % 60;
+        if mins > 0 {
+            format!("{} hour{} and {} minute{}", hours, if hours > 1 { "s" } else { "" }, mins, if mins > 1 { "s" } else { "" })
         } else {
-            format!("{} minute{}", duration_minutes, if duration_minutes > 1 { "s" } else { "" })
-        };
+            format!("{} hour{}", hours, if hours > 1 { "s" } else { "" })
+        }
+    } else {
+        format!("{} minute{}", duration_minutes, if duration_minutes > 1 { "s" } else { "" })
+    };
 
-        let _ = Command::new("notify-send")
-            .arg("-u")
-            .arg("normal")
-            .arg("-t")
-            .arg("5000")
-            .arg("-i")
-            .arg("emblem-default")
-            .arg("Focus Session Complete!")
-            .arg(format!("Congratulations! You completed a {} focus session", duration_text))
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .spawn();
-    }
-    
-    Ok("Notification sent".to_string())
+    notifications::notify(Notification {
+        kind: Kind::SessionComplete,
+        icon: "emblem-default",
+        summary: "Focus Session Complete!",
+        body: &format!("Congratulations! You completed a {} focus session", duration_text),
+        actions: &[],
+    });
 }
@@ -0,0 +1,135 @@
+use std::collections::BTreeSet;
+use std::net::{IpAddr, ToSocketAddrs};
+use std::process::Command;
+use std::thread;
+
+use serde::Serialize;
+
+/// `ss` filters grow with every address; keep each invocation well below
+/// the kernel's argument limits.
+const ADDRESSES_PER_CALL: usize = 64;
+const RESOLVER_THREADS: usize = 16;
+/// Resolving every entry of an imported blocklist would hold up the start
+/// of a session for minutes; hosts past this many keep their connections
+/// until the browser drops them.
+const MAX_RESOLVED_HOSTS: usize = 128;
+
+/// Runs `ss -K` for the addresses it is given. sudo allows this helper
+/// rather than `ss` itself, since other `ss` options write files as root.
+pub const KILL_HELPER: &str = "/usr/local/sbin/brisk-kill-connections";
+pub const KILL_HELPER_SCRIPT: &str = r#"#!/bin/sh
+# Installed by Brisk. Destroys the TCP connections to the IP addresses it
+# is given and accepts nothing else, so sudo can allow it without a
+# password.
+PATH=/usr/sbin:/usr/bin:/sbin:/bin
+filter=""
+for ip in "$@"; do
+    case "$ip" in
+        "" | *[!0-9A-Fa-f:.]*) echo "Not an IP address: $ip" >&2; exit 2 ;;
+        *.* | *:*) ;;
+        *) echo "Not an IP address: $ip" >&2; exit 2 ;;
+    esac
+    filter="$filter${filter:+ or }dst $ip"
+done
+[ -n "$filter" ] || exit 0
+exec ss -t -K "( $filter )"
+"#;
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectionReport {
+    pub addresses: usize,
+    pub killed: usize,
+    pub error: Option<String>,
+}
+
+impl ConnectionReport {
+    pub fn summary(&self) -> String {
+        match &self.error {
+            Some(e) => format!(
+                "Could not close open connections, restart your browser to apply blocking ({})",
+                e
+            ),
+            None => format!("Closed {} open connections", self.killed),
+        }
+    }
+}
+
+/// Resolves the hosts while they still point at their real servers, so
+/// this must run before the hosts file is rewritten. Lookups run on a few
+/// threads at a time since each can wait on a slow upstream resolver.
+pub fn resolve_addresses(hosts: &[String]) -> Vec<IpAddr> {
+    let mut addresses = BTreeSet::new();
+
+    if hosts.len() > MAX_RESOLVED_HOSTS {
+        println!(
+            "Closing connections to the first {} of {} blocked hosts only",
+            MAX_RESOLVED_HOSTS,
+            hosts.len()
+        );
+    }
+    let hosts = &hosts[..hosts.len().min(MAX_RESOLVED_HOSTS)];
+
+    for batch in hosts.chunks(RESOLVER_THREADS) {
+        thread::scope(|scope| {
+            let lookups: Vec<_> = batch
+                .iter()
+                .map(|host| {
+                    scope.spawn(move || {
+                        (host.as_str(), 443)
+                            .to_socket_addrs()
+                            .map(|addrs| addrs.map(|a| a.ip()).collect::<Vec<_>>())
+                            .unwrap_or_default()
+                    })
+                })
+                .collect();
+
+            for lookup in lookups {
+                addresses.extend(lookup.join().unwrap_or_default());
+            }
+        });
+    }
+
+    // Entries from an earlier session's hosts block resolve to these
+    addresses
+        .into_iter()
+        .filter(|ip| !ip.is_unspecified() && !ip.is_loopback())
+        .collect()
+}
+
+/// Destroys established TCP sockets to the given addresses with `ss -K`, so
+/// browsers reconnect and hit the new hosts entries instead of reusing
+/// keep-alive connections. Needs the helper installed with passwordless
+/// sudo and a kernel with `CONFIG_INET_DIAG_DESTROY`, which all major
+/// distributions enable.
+pub fn kill_connections(addresses: &[IpAddr]) -> ConnectionReport {
+    let mut report = ConnectionReport {
+        addresses: addresses.len(),
+        ..Default::default()
+    };
+
+    for chunk in addresses.chunks(ADDRESSES_PER_CALL) {
+        let output = Command::new("sudo")
+            .arg("-n")
+            .arg(KILL_HELPER)
+            .args(chunk.iter().map(|ip| ip.to_string()))
+            .output();
+
+        match output {
+            Ok(o) if o.status.success() => {
+                // ss lists each socket it destroyed below a header line
+                let listed = String::from_utf8_lossy(&o.stdout).lines().count();
+                report.killed += listed.saturating_sub(1);
+            }
+            Ok(o) => {
+                report.error = Some(String::from_utf8_lossy(&o.stderr).trim().to_string());
+                break;
+            }
+            Err(e) => {
+                report.error = Some(format!("Failed to run {}: {}", KILL_HELPER, e));
+                break;
+            }
+        }
+    }
+
+    report
+}
@@ -0,0 +1,547 @@
+use std::sync::Arc;
+use std::thread;
+
+use chrono::Utc;
+use tauri::{AppHandle, Listener, Manager};
+use zbus::blocking::connection::Builder;
+use zbus::blocking::fdo::DBusProxy;
+use zbus::blocking::Connection;
+use zbus::fdo;
+use zbus::names::BusName;
+use zbus::object_server::SignalEmitter;
+
+use crate::commands::{self, SessionRequest};
+use crate::profiles::{EnforcementMode, ProfileStore};
+use crate::reminders::ReminderSettings;
+use crate::session::{ActiveSession, SessionState};
+
+pub const BUS_NAME: &str = "org.brisk.Focus";
+pub const OBJECT_PATH: &str = "/org/brisk/Focus";
+
+/// Client side of `org.brisk.Focus`, for the CLI to drive a running app.
+#[zbus::proxy(
+    interface = "org.brisk.Focus",
+    default_service = "org.brisk.Focus",
+    default_path = "/org/brisk/Focus"
+)]
+pub trait Focus {
+    fn start_session(
+        &self,
+        goal: &str,
+        duration: u64,
+        sites: &[String],
+        apps: &[String],
+    ) -> zbus::Result<String>;
+
+    fn start_session_with_options(
+        &self,
+        goal: &str,
+        duration: u64,
+        sites: &[String],
+        apps: &[String],
+        profile: &str,
+        strict: bool,
+    ) -> zbus::Result<String>;
+
+    fn stop_session(&self) -> zbus::Result<String>;
+
+    fn get_status(&self) -> zbus::Result<(bool, String, i64, i64, bool)>;
+
+    fn get_session(&self) -> zbus::Result<String>;
+}
+
+/// Connects to the running app, or returns `None` when it is not running
+/// and the caller has to act on its own.
+pub fn connect_to_app() -> Option<FocusProxyBlocking<'static>> {
+    let connection = Connection::session().ok()?;
+    let dbus = DBusProxy::new(&connection).ok()?;
+    let name = BusName::try_from(BUS_NAME).ok()?;
+    if !dbus.name_has_owner(name).unwrap_or(false) {
+        return None;
+    }
+    FocusProxyBlocking::new(&connection).ok()
+}
+
+/// The message of an error the app replied with, without the D-Bus error
+/// name in front.
+pub fn reply_error(error: zbus::Error) -> String {
+    match error {
+        zbus::Error::MethodError(_, Some(message), _) => message,
+        other => other.to_string(),
+    }
+}
+
+/// The session state behind the interface: the app itself, or a fake in
+/// tests.
+trait Sessions: Send + Sync {
+    fn current(&self) -> Option<ActiveSession>;
+    fn start(&self, request: SessionRequest) -> Result<String, String>;
+    fn ensure_unlocked(&self) -> Result<(), String>;
+    fn end(&self) -> Result<String, String>;
+    fn profile_reminders(&self, profile: &str) -> Option<ReminderSettings>;
+}
+
+impl Sessions for AppHandle {
+    fn current(&self) -> Option<ActiveSession> {
+        self.state::<SessionState>().current()
+    }
+
+    fn start(&self, request: SessionRequest) -> Result<String, String> {
+        commands::start_session(self, request)
+    }
+
+    fn ensure_unlocked(&self) -> Result<(), String> {
+        self.state::<SessionState>()
+            .ensure_unlocked(Utc::now().timestamp())
+    }
+
+    fn end(&self) -> Result<String, String> {
+        commands::end_session(self).map(|report| {
+            format!(
+                "Sites and apps unblocked successfully. {}",
+                report.summary()
+            )
+        })
+    }
+
+    fn profile_reminders(&self, profile: &str) -> Option<ReminderSettings> {
+        self.state::<ProfileStore>()
+            .get(profile)
+            .map(|profile| profile.reminders)
+    }
+}
+
+/// The `org.brisk.Focus` interface, backed by the same session state as
+/// the Tauri commands.
+struct FocusService {
+    sessions: Arc<dyn Sessions>,
+}
+
+impl FocusService {
+    /// Runs `work` on a blocking worker. Starting and ending sessions runs
+    /// sudo, flushes DNS caches and resolves hosts, and the bus has to keep
+    /// answering other calls meanwhile.
+    async fn on_worker<T, F>(&self, work: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn Sessions) -> Result<T, String> + Send + 'static,
+    {
+        let sessions = Arc::clone(&self.sessions);
+        tauri::async_runtime::spawn_blocking(move || work(sessions.as_ref()))
+            .await
+            .map_err(|e| format!("Session worker failed: {}", e))?
+    }
+}
+
+#[zbus::interface(name = "org.brisk.Focus")]
+impl FocusService {
+    /// Starts a session of `duration` seconds blocking `sites` and `apps`.
+    async fn start_session(
+        &self,
+        goal: String,
+        duration: u64,
+        sites: Vec<String>,
+        apps: Vec<String>,
+    ) -> fdo::Result<String> {
+        self.start_session_with_options(goal, duration, sites, apps, String::new(), false)
+            .await
+    }
+
+    /// Like `StartSession`, also naming the profile the session comes from,
+    /// or an empty string for none, and whether it runs in strict mode.
+    async fn start_session_with_options(
+        &self,
+        goal: String,
+        duration: u64,
+        sites: Vec<String>,
+        apps: Vec<String>,
+        profile: String,
+        strict: bool,
+    ) -> fdo::Result<String> {
+        if duration == 0 {
+            return Err(fdo::Error::InvalidArgs(
+                "Duration must be greater than zero".to_string(),
+            ));
+        }
+
+        let profile = Some(profile).filter(|id| !id.is_empty());
+        let reminders = profile
+            .as_deref()
+            .and_then(|id| self.sessions.profile_reminders(id))
+            .unwrap_or_default();
+
+        let request = SessionRequest {
+            goal,
+            duration,
+            blocked_things: sites,
+            blocked_apps: apps,
+            profile,
+            enforcement: if strict {
+                EnforcementMode::Strict
+            } else {
+                EnforcementMode::Standard
+            },
+            schedule: None,
+            pomodoro: None,
+            reminders,
+        };
+        self.on_worker(move |sessions| sessions.start(request))
+            .await
+            .map_err(fdo::Error::Failed)
+    }
+
+    async fn stop_session(&self) -> fdo::Result<String> {
+        if self.sessions.current().is_none() {
+            return Err(fdo::Error::Failed("No active session".to_string()));
+        }
+        self.sessions
+            .ensure_unlocked()
+            .map_err(fdo::Error::AccessDenied)?;
+
+        self.on_worker(|sessions| sessions.end())
+            .await
+            .map_err(fdo::Error::Failed)
+    }
+
+    /// Returns `(active, goal, started_at, ends_at, paused)`, with empty
+    /// values when no session is running.
+    fn get_status(&self) -> (bool, String, i64, i64, bool) {
+        match self.sessions.current() {
+            Some(session) => (
+                true,
+                session.goal,
+                session.started_at,
+                session.ends_at,
+                session.pause.is_some(),
+            ),
+            None => (false, String::new(), 0, 0, false),
+        }
+    }
+
+    /// The active session as JSON, `null` when none is running.
+    fn get_session(&self) -> fdo::Result<String> {
+        let session = self.sessions.current();
+        serde_json::to_string(&session).map_err(|e| fdo::Error::Failed(e.to_string()))
+    }
+
+    #[zbus(signal)]
+    async fn session_started(
+        emitter: &SignalEmitter<'_>,
+        goal: &str,
+        ends_at: i64,
+    ) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    async fn session_ended(emitter: &SignalEmitter<'_>, goal: &str) -> zbus::Result<()>;
+}
+
+/// Claims `org.brisk.Focus` on the session bus and relays session events
+/// as signals.
+pub fn serve(app: &AppHandle) -> Result<Connection, String> {
+    let connection = Builder::session()
+        .and_then(|builder| serve_on(builder, app.clone()))
+        .map_err(|e| format!("Failed to register {} on the session bus: {}", BUS_NAME, e))?;
+
+    let started = connection.clone();
+    app.listen_any("session-started", move |event| {
+        let Ok(session) = serde_json::from_str::<ActiveSession>(event.payload()) else {
+            return;
+        };
+        let connection = started.clone();
+        // Sessions started over D-Bus emit this from the worker their call
+        // waits on, which must not be blocked on sending the signal
+        thread::spawn(move || {
+            emit(&connection, |emitter| async move {
+                FocusService::session_started(&emitter, &session.goal, session.ends_at).await
+            })
+        });
+    });
+
+    let ended = connection.clone();
+    app.listen_any("session-ended", move |event| {
+        let Ok(session) = serde_json::from_str::<ActiveSession>(event.payload()) else {
+            return;
+        };
+        let connection = ended.clone();
+        thread::spawn(move || {
+            emit(&connection, |emitter| async move {
+                FocusService::session_ended(&emitter, &session.goal).await
+            })
+        });
+    });
+
+    Ok(connection)
+}
+
+fn serve_on(builder: Builder<'_>, sessions: impl Sessions + 'static) -> zbus::Result<Connection> {
+    let service = FocusService {
+        sessions: Arc::new(sessions),
+    };
+    builder
+        .name(BUS_NAME)?
+        .serve_at(OBJECT_PATH, service)?
+        .build()
+}
+
+fn emit<F, Fut>(connection: &Connection, signal: F)
+where
+    F: FnOnce(SignalEmitter<'static>) -> Fut,
+    Fut: std::future::Future<Output = zbus::Result<()>>,
+{
+    let result = SignalEmitter::new(connection.inner(), OBJECT_PATH)
+        .and_then(|emitter| zbus::block_on(signal(emitter)));
+
+    if let Err(e) = result {
+        eprintln!("Failed to emit D-Bus signal: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::path::PathBuf;
+    use std::process::{Child, Command, Stdio};
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    /// A dbus-daemon of its own for each test, so nothing reaches the
+    /// session bus of whoever runs them.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        /// `None` when dbus-daemon is not installed.
+        fn start() -> Option<Self> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        fn serve(&self, sessions: FakeSessions) -> Connection {
+            serve_on(Builder::address(self.address.as_str()).unwrap(), sessions).unwrap()
+        }
+
+        fn client(&self) -> FocusProxyBlocking<'static> {
+            let connection = Builder::address(self.address.as_str())
+                .unwrap()
+                .build()
+                .unwrap();
+            FocusProxyBlocking::new(&connection).unwrap()
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    #[derive(Default)]
+    struct FakeSessions {
+        current: Mutex<Option<ActiveSession>>,
+        reminders: Option<ReminderSettings>,
+        /// How long starting takes, standing in for sudo and DNS.
+        start_delay: Duration,
+    }
+
+    impl Sessions for FakeSessions {
+        fn current(&self) -> Option<ActiveSession> {
+            self.current.lock().unwrap().clone()
+        }
+
+        fn start(&self, request: SessionRequest) -> Result<String, String> {
+            thread::sleep(self.start_delay);
+            let started_at = Utc::now().timestamp();
+            *self.current.lock().unwrap() = Some(ActiveSession {
+                goal: request.goal.clone(),
+                started_at,
+                ends_at: started_at + request.duration as i64,
+                profile: request.profile,
+                enforcement: request.enforcement,
+                schedule: request.schedule,
+                record: PathBuf::new(),
+                blocked_things: request.blocked_things,
+                blocked_apps: request.blocked_apps,
+                pause: None,
+                paused_for: 0,
+                pause_budget: 0,
+                unlocked: false,
+                reminders: request.reminders,
+            });
+            Ok(format!("Started {}", request.goal))
+        }
+
+        fn ensure_unlocked(&self) -> Result<(), String> {
+            match self.current() {
+                Some(session) if session.is_locked(Utc::now().timestamp()) => {
+                    Err("Strict mode is on".to_string())
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn end(&self) -> Result<String, String> {
+            let session = self
+                .current
+                .lock()
+                .unwrap()
+                .take()
+                .ok_or("No active session")?;
+            Ok(format!("Ended {}", session.goal))
+        }
+
+        fn profile_reminders(&self, _profile: &str) -> Option<ReminderSettings> {
+            self.reminders.clone()
+        }
+    }
+
+    fn sites() -> Vec<String> {
+        vec!["example.com".to_string()]
+    }
+
+    macro_rules! private_bus {
+        () => {
+            match PrivateBus::start() {
+                Some(bus) => bus,
+                None => {
+                    eprintln!("dbus-daemon is not installed, skipping");
+                    return;
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn session_is_started_reported_and_stopped() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions::default());
+        let app = bus.client();
+
+        assert_eq!(
+            app.get_status().unwrap(),
+            (false, String::new(), 0, 0, false)
+        );
+
+        let message = app.start_session("Write", 1500, &sites(), &[]).unwrap();
+        assert_eq!(message, "Started Write");
+
+        let (active, goal, started_at, ends_at, paused) = app.get_status().unwrap();
+        assert!(active);
+        assert_eq!(goal, "Write");
+        assert_eq!(ends_at - started_at, 1500);
+        assert!(!paused);
+
+        assert_eq!(app.stop_session().unwrap(), "Ended Write");
+        assert!(!app.get_status().unwrap().0);
+    }
+
+    #[test]
+    fn session_is_returned_as_json() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions::default());
+        let app = bus.client();
+
+        assert_eq!(app.get_session().unwrap(), "null");
+
+        app.start_session("Write", 1500, &sites(), &[]).unwrap();
+        let session: ActiveSession = serde_json::from_str(&app.get_session().unwrap()).unwrap();
+        assert_eq!(session.goal, "Write");
+        assert_eq!(session.blocked_things, sites());
+    }
+
+    #[test]
+    fn zero_duration_is_rejected() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions::default());
+        let app = bus.client();
+
+        let error = app.start_session("Write", 0, &sites(), &[]).unwrap_err();
+        assert_eq!(reply_error(error), "Duration must be greater than zero");
+        assert!(!app.get_status().unwrap().0);
+    }
+
+    #[test]
+    fn stopping_without_a_session_fails() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions::default());
+        let app = bus.client();
+
+        let error = app.stop_session().unwrap_err();
+        assert_eq!(reply_error(error), "No active session");
+    }
+
+    #[test]
+    fn strict_session_cannot_be_stopped() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions::default());
+        let app = bus.client();
+
+        app.start_session_with_options("Write", 1500, &sites(), &[], "", true)
+            .unwrap();
+
+        let error = app.stop_session().unwrap_err();
+        assert_eq!(reply_error(error), "Strict mode is on");
+        assert!(app.get_status().unwrap().0);
+    }
+
+    #[test]
+    fn profile_reminders_are_used() {
+        let bus = private_bus!();
+        let reminders = ReminderSettings {
+            warnings: vec![],
+            halfway: false,
+            goal_interval: Some(600),
+        };
+        let _service = bus.serve(FakeSessions {
+            reminders: Some(reminders.clone()),
+            ..Default::default()
+        });
+        let app = bus.client();
+
+        app.start_session_with_options("Write", 1500, &sites(), &[], "work", false)
+            .unwrap();
+
+        let session: ActiveSession = serde_json::from_str(&app.get_session().unwrap()).unwrap();
+        assert_eq!(session.profile.as_deref(), Some("work"));
+        assert_eq!(session.reminders, reminders);
+    }
+
+    #[test]
+    fn status_is_answered_while_a_session_starts() {
+        let bus = private_bus!();
+        let _service = bus.serve(FakeSessions {
+            start_delay: Duration::from_secs(2),
+            ..Default::default()
+        });
+        let starter = bus.client();
+        let app = bus.client();
+
+        let starting =
+            thread::spawn(move || starter.start_session("Write", 1500, &sites(), &[]).unwrap());
+        thread::sleep(Duration::from_millis(200));
+
+        let asked = Instant::now();
+        assert!(!app.get_status().unwrap().0);
+        assert!(asked.elapsed() < Duration::from_secs(1));
+
+        assert_eq!(starting.join().unwrap(), "Started Write");
+        assert!(app.get_status().unwrap().0);
+    }
+}
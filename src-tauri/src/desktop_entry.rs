@@ -0,0 +1,587 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+const MAIN_GROUP: &str = "Desktop Entry";
+
+/// A parsed `[Desktop Entry]` group, following the freedesktop.org
+/// Desktop Entry Specification. Keys from other groups such as
+/// `[Desktop Action new-window]` are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    pub entry_type: String,
+    pub name: String,
+    pub generic_name: Option<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub icon: Option<String>,
+    pub categories: Vec<String>,
+    pub keywords: Vec<String>,
+    pub startup_wm_class: Option<String>,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+    pub no_display: bool,
+    pub hidden: bool,
+    /// `X-SnapInstanceName`, set by snapd on the entries it exports.
+    pub snap_instance_name: Option<String>,
+}
+
+impl DesktopEntry {
+    /// Parses a desktop file using the locale from the environment for
+    /// localized keys like `Name[de]=`.
+    pub fn parse(content: &str) -> Option<Self> {
+        Self::parse_with_locale(content, current_locale().as_deref())
+    }
+
+    pub fn parse_with_locale(content: &str, locale: Option<&str>) -> Option<Self> {
+        let group = read_main_group(content)?;
+        let locale_keys = locale.map(locale_variants).unwrap_or_default();
+
+        let string = |key: &str| group.get(key).map(|v| unescape_string(v));
+        let localized = |key: &str| {
+            locale_keys
+                .iter()
+                .find_map(|l| group.get(&format!("{}[{}]", key, l)))
+                .or_else(|| group.get(key))
+                .map(|v| unescape_string(v))
+        };
+        let list = |key: &str| group.get(key).map(|v| split_list(v)).unwrap_or_default();
+        let localized_list = |key: &str| {
+            locale_keys
+                .iter()
+                .find_map(|l| group.get(&format!("{}[{}]", key, l)))
+                .or_else(|| group.get(key))
+                .map(|v| split_list(v))
+                .unwrap_or_default()
+        };
+        let boolean = |key: &str| group.get(key).is_some_and(|v| v.trim() == "true");
+
+        Some(Self {
+            entry_type: string("Type").unwrap_or_default(),
+            name: localized("Name")?,
+            generic_name: localized("GenericName"),
+            exec: string("Exec"),
+            try_exec: string("TryExec"),
+            icon: localized("Icon"),
+            categories: list("Categories"),
+            keywords: localized_list("Keywords"),
+            startup_wm_class: string("StartupWMClass"),
+            only_show_in: list("OnlyShowIn"),
+            not_show_in: list("NotShowIn"),
+            no_display: boolean("NoDisplay"),
+            hidden: boolean("Hidden"),
+            snap_instance_name: string("X-SnapInstanceName"),
+        })
+    }
+
+    pub fn is_application(&self) -> bool {
+        self.entry_type == "Application"
+    }
+
+    /// Whether the entry should be listed on a desktop identified by the
+    /// `XDG_CURRENT_DESKTOP` names in `desktops`.
+    pub fn should_show_in(&self, desktops: &[String]) -> bool {
+        if self.no_display || self.hidden {
+            return false;
+        }
+
+        if !self.only_show_in.is_empty()
+            && !self.only_show_in.iter().any(|d| desktops.contains(d))
+        {
+            return false;
+        }
+
+        !self.not_show_in.iter().any(|d| desktops.contains(d))
+    }
+
+    /// Checks `TryExec` against the filesystem and `$PATH`. Entries without
+    /// a `TryExec` key are always considered installed.
+    pub fn is_installed(&self) -> bool {
+        match &self.try_exec {
+            Some(try_exec) => find_in_path(try_exec),
+            None => true,
+        }
+    }
+
+    /// The `Exec` command line split into arguments, with quoting resolved
+    /// and field codes (`%U`, `%f`, ...) expanded or removed.
+    pub fn exec_args(&self) -> Vec<String> {
+        let Some(exec) = &self.exec else {
+            return Vec::new();
+        };
+
+        split_exec(exec)
+            .into_iter()
+            .flat_map(|arg| expand_field_codes(&arg, self))
+            .collect()
+    }
+
+    /// The program the entry launches, as written in `Exec`. Wrappers like
+    /// `env FOO=1 app` resolve to `app` rather than `env`.
+    pub fn program(&self) -> Option<String> {
+        let args = self.exec_args();
+        let mut args = args.iter();
+        let mut program = args.next()?;
+
+        if file_name(program) == "env" {
+            program = loop {
+                let arg = args.next()?;
+                if arg == "-u" || arg == "--unset" || arg == "-C" || arg == "--chdir" {
+                    args.next()?;
+                } else if arg.starts_with('-') || arg.contains('=') {
+                    continue;
+                } else {
+                    break arg;
+                }
+            };
+        }
+
+        Some(program.clone())
+    }
+
+    /// [`program`](Self::program) as a bare file name.
+    pub fn executable(&self) -> Option<String> {
+        let program = self.program()?;
+        let name = file_name(&program);
+        (!name.is_empty()).then(|| name.to_string())
+    }
+}
+
+/// Desktop names from `XDG_CURRENT_DESKTOP`, e.g. `["ubuntu", "GNOME"]`.
+pub fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|v| !v.is_empty())
+        .filter(|v| v != "C" && v != "POSIX")
+}
+
+/// Expands a locale like `de_DE.UTF-8@euro` into the keys to try, in the
+/// order required by the spec: `de_DE@euro`, `de_DE`, `de@euro`, `de`.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split('.').next().unwrap_or(rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut variants = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    variants.push(lang.to_string());
+    variants
+}
+
+fn read_main_group(content: &str) -> Option<HashMap<String, String>> {
+    let mut group = None;
+    let mut in_main = false;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            if in_main {
+                break;
+            }
+            in_main = &line[1..line.len() - 1] == MAIN_GROUP;
+            if in_main {
+                group = Some(HashMap::new());
+            }
+            continue;
+        }
+
+        if !in_main {
+            continue;
+        }
+
+        if let (Some(group), Some((key, value))) = (group.as_mut(), line.split_once('=')) {
+            // Duplicate keys are invalid; keep the first like most parsers do
+            group
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+
+    group
+}
+
+fn unescape_string(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some('\\') => result.push('\\'),
+            Some(other) => {
+                result.push('\\');
+                result.push(other);
+            }
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(';') => current.push(';'),
+                Some(other) => {
+                    current.push('\\');
+                    current.push(other);
+                }
+                None => current.push('\\'),
+            },
+            ';' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items
+        .iter()
+        .map(|item| unescape_string(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// Splits an `Exec` value into arguments. Arguments may be wrapped in
+/// double quotes, inside which `\"`, `` \` ``, `\$` and `\\` are escapes.
+fn split_exec(exec: &str) -> Vec<String> {
+    let exec = unescape_string(exec);
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut has_arg = false;
+    let mut in_quotes = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            }
+            '\\' if in_quotes => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ' ' | '\t' | '\n' if !in_quotes => {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+    }
+    if has_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+/// Expands field codes in a single argument into the arguments it stands
+/// for. `%i` becomes `--icon <Icon>`; an argument that was nothing but
+/// codes without a value, or that would carry files or URLs, is left out.
+fn expand_field_codes(arg: &str, entry: &DesktopEntry) -> Vec<String> {
+    if arg == "%i" {
+        return match &entry.icon {
+            Some(icon) => vec!["--icon".to_string(), icon.clone()],
+            None => Vec::new(),
+        };
+    }
+
+    let mut result = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+    let mut only_codes = true;
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            only_codes = false;
+            continue;
+        }
+        match chars.next() {
+            Some('%') => {
+                result.push('%');
+                only_codes = false;
+            }
+            Some('c') => {
+                result.push_str(&entry.name);
+                only_codes = false;
+            }
+            // Nothing is opened when launching from the app list, so an
+            // argument like `--open=%f` would be left dangling
+            Some('f' | 'F' | 'u' | 'U') => return Vec::new(),
+            // The desktop file location is only known at launch time and
+            // `%i` only counts on its own; deprecated codes are dropped too.
+            Some(_) | None => {}
+        }
+    }
+
+    if only_codes && result.is_empty() {
+        Vec::new()
+    } else {
+        vec![result]
+    }
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+fn find_in_path(program: &str) -> bool {
+    if program.contains('/') {
+        return Path::new(program).is_file();
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIREFOX: &str = r#"[Desktop Entry]
+Version=1.0
+Name=Firefox Web Browser
+Name[de]=Firefox-Webbrowser
+Name[pt_BR]=Navegador Web Firefox
+GenericName=Web Browser
+GenericName[de]=Webbrowser
+Keywords=Internet;WWW;Browser;Web;Explorer;
+Keywords[de]=Internet;WWW;Browser;Web;Explorer;Webseite;
+Exec=firefox %u
+Terminal=false
+Type=Application
+Icon=firefox
+Categories=GNOME;GTK;Network;WebBrowser;
+MimeType=text/html;text/xml;application/xhtml+xml;
+StartupNotify=true
+Actions=new-window;new-private-window;
+
+[Desktop Action new-window]
+Name=Open a New Window
+Exec=firefox -new-window
+"#;
+
+    const SPOTIFY_FLATPAK: &str = r#"[Desktop Entry]
+Type=Application
+Name=Spotify
+GenericName=Music Player
+Icon=com.spotify.Client
+TryExec=spotify
+Exec=env PULSE_PROP_media.role=music /usr/bin/flatpak run --branch=stable --arch=x86_64 --command=spotify --file-forwarding com.spotify.Client @@u %U @@
+Terminal=false
+MimeType=x-scheme-handler/spotify;
+Categories=Audio;Music;Player;AudioVideo;
+StartupWMClass=spotify
+X-Flatpak=com.spotify.Client
+"#;
+
+    const CODE: &str = r#"[Desktop Entry]
+Name=Visual Studio Code
+Comment=Code Editing. Redefined.
+GenericName=Text Editor
+Exec=/usr/share/code/code --unity-launch %F
+Icon=vscode
+Type=Application
+StartupNotify=false
+StartupWMClass=Code
+Categories=TextEditor;Development;IDE;
+Keywords=vscode;
+"#;
+
+    fn parse(content: &str, locale: Option<&str>) -> DesktopEntry {
+        DesktopEntry::parse_with_locale(content, locale).unwrap()
+    }
+
+    #[test]
+    fn localized_keys_follow_the_locale() {
+        assert_eq!(
+            parse(FIREFOX, Some("de_DE.UTF-8")).name,
+            "Firefox-Webbrowser"
+        );
+        assert_eq!(
+            parse(FIREFOX, Some("pt_BR.UTF-8")).name,
+            "Navegador Web Firefox"
+        );
+        assert_eq!(
+            parse(FIREFOX, Some("fr_FR.UTF-8")).name,
+            "Firefox Web Browser"
+        );
+        assert_eq!(parse(FIREFOX, None).name, "Firefox Web Browser");
+
+        let german = parse(FIREFOX, Some("de_AT@euro"));
+        assert_eq!(german.generic_name.as_deref(), Some("Webbrowser"));
+        assert!(german.keywords.contains(&"Webseite".to_string()));
+    }
+
+    #[test]
+    fn locale_variants_are_tried_most_specific_first() {
+        assert_eq!(
+            locale_variants("sr_RS.UTF-8@latin"),
+            ["sr_RS@latin", "sr_RS", "sr@latin", "sr"]
+        );
+        assert_eq!(locale_variants("fi"), ["fi"]);
+    }
+
+    #[test]
+    fn other_groups_are_ignored() {
+        let entry = parse(FIREFOX, None);
+        assert_eq!(entry.exec.as_deref(), Some("firefox %u"));
+        assert_eq!(entry.categories, ["GNOME", "GTK", "Network", "WebBrowser"]);
+        assert!(entry.is_application());
+    }
+
+    #[test]
+    fn env_wrappers_resolve_to_the_program() {
+        let entry = parse(SPOTIFY_FLATPAK, None);
+        assert_eq!(entry.program().as_deref(), Some("/usr/bin/flatpak"));
+        assert_eq!(entry.executable().as_deref(), Some("flatpak"));
+
+        let entry = parse(
+            "[Desktop Entry]\nName=Tool\nType=Application\nExec=env -u DISPLAY FOO=1 BAR=2 tool --flag\n",
+            None,
+        );
+        assert_eq!(entry.program().as_deref(), Some("tool"));
+    }
+
+    #[test]
+    fn quoted_arguments_are_split_and_unescaped() {
+        assert_eq!(
+            split_exec(r#""/opt/My App/app" --title "say \"hi\"" plain"#),
+            ["/opt/My App/app", "--title", "say \"hi\"", "plain"]
+        );
+        assert_eq!(split_exec(r#"app "" next"#), ["app", "", "next"]);
+        assert_eq!(
+            split_exec(r#"sh -c "echo \\$HOME""#),
+            ["sh", "-c", "echo $HOME"]
+        );
+    }
+
+    #[test]
+    fn field_codes_are_expanded_or_dropped() {
+        let entry = parse(CODE, None);
+        assert_eq!(
+            entry.exec_args(),
+            ["/usr/share/code/code", "--unity-launch"]
+        );
+
+        let entry = parse(
+            "[Desktop Entry]\nName=Viewer\nType=Application\nExec=viewer --name %c %U 100%% %k\n",
+            None,
+        );
+        assert_eq!(entry.exec_args(), ["viewer", "--name", "Viewer", "100%"]);
+    }
+
+    #[test]
+    fn icon_field_code_expands_to_an_icon_option() {
+        let entry = parse(
+            "[Desktop Entry]\nName=Viewer\nType=Application\nIcon=viewer-icon\nExec=viewer %i --title=%i%c\n",
+            None,
+        );
+        assert_eq!(
+            entry.exec_args(),
+            ["viewer", "--icon", "viewer-icon", "--title=Viewer"]
+        );
+
+        let entry = parse(
+            "[Desktop Entry]\nName=Viewer\nType=Application\nExec=viewer %i --flag\n",
+            None,
+        );
+        assert_eq!(entry.exec_args(), ["viewer", "--flag"]);
+    }
+
+    #[test]
+    fn arguments_carrying_files_or_urls_are_dropped_whole() {
+        let entry = parse(
+            "[Desktop Entry]\nName=Viewer\nType=Application\nExec=viewer --open=%f --urls=%U file:%u --keep\n",
+            None,
+        );
+        assert_eq!(entry.exec_args(), ["viewer", "--keep"]);
+    }
+
+    #[test]
+    fn hidden_and_no_display_entries_are_not_shown() {
+        let desktops = vec!["GNOME".to_string()];
+        assert!(parse(CODE, None).should_show_in(&desktops));
+
+        let hidden = format!("{}Hidden=true\n", CODE);
+        assert!(parse(&hidden, None).hidden);
+        assert!(!parse(&hidden, None).should_show_in(&desktops));
+
+        let no_display = format!("{}NoDisplay=true\n", CODE);
+        assert!(!parse(&no_display, None).should_show_in(&desktops));
+    }
+
+    #[test]
+    fn show_in_lists_are_respected() {
+        let only_kde = format!("{}OnlyShowIn=KDE;\n", CODE);
+        assert!(!parse(&only_kde, None).should_show_in(&["GNOME".to_string()]));
+        assert!(parse(&only_kde, None).should_show_in(&["KDE".to_string()]));
+
+        let not_gnome = format!("{}NotShowIn=GNOME;Unity;\n", CODE);
+        assert!(
+            !parse(&not_gnome, None).should_show_in(&["ubuntu".to_string(), "GNOME".to_string()])
+        );
+    }
+
+    #[test]
+    fn entries_without_a_name_are_rejected() {
+        assert!(
+            DesktopEntry::parse_with_locale("[Desktop Entry]\nType=Application\n", None).is_none()
+        );
+        assert!(DesktopEntry::parse_with_locale("Name=Orphan\n", None).is_none());
+    }
+
+    #[test]
+    fn lists_keep_escaped_separators() {
+        assert_eq!(split_list(r"a\;b;c;;"), ["a;b", "c"]);
+        assert_eq!(unescape_string(r"one\stwo\\three"), r"one two\three");
+    }
+}
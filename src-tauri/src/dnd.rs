@@ -0,0 +1,483 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::notifications;
+use crate::store;
+use crate::xdg;
+
+const GNOME_SCHEMA: &str = "org.gnome.desktop.notifications";
+const GNOME_KEY: &str = "show-banners";
+/// The mako mode Brisk switches on. Users define what it does in their
+/// mako config, typically `[mode=do-not-disturb]` with `invisible=1`,
+/// followed by `[mode=do-not-disturb app-name=Brisk]` with `invisible=0`
+/// so Brisk's own reminders still show.
+const MAKO_MODE: &str = "do-not-disturb";
+
+/// Runs the desktop tools that switch Do-Not-Disturb, so the backends can
+/// be driven by a fake in place of the real commands.
+pub trait CommandRunner: Send + Sync {
+    /// Returns the trimmed stdout of `program`, or an error when it cannot
+    /// be started or exits unsuccessfully.
+    fn run(&self, program: &str, args: &[&str]) -> Result<String, String>;
+}
+
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> Result<String, String> {
+        let output = Command::new(program)
+            .args(args)
+            .output()
+            .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+        if !output.status.success() {
+            return Err(format!(
+                "{} {} failed: {}",
+                program,
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+}
+
+/// Holds back notifications on desktops that do it over D-Bus rather than
+/// through a command, as KDE Plasma does.
+pub trait Inhibitor: Send + Sync {
+    fn inhibit(&self, reason: &str) -> Result<u32, String>;
+    fn uninhibit(&self, cookie: u32) -> Result<(), String>;
+}
+
+pub struct NotificationInhibitor;
+
+impl Inhibitor for NotificationInhibitor {
+    fn inhibit(&self, reason: &str) -> Result<u32, String> {
+        notifications::inhibit(reason)
+    }
+
+    fn uninhibit(&self, cookie: u32) -> Result<(), String> {
+        notifications::uninhibit(cookie)
+    }
+}
+
+/// Each desktop's notification state from before the session, for the
+/// desktops Brisk changed. Persisted so a restart mid-session still
+/// restores it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Previous {
+    show_banners: Option<bool>,
+    dunst_paused: Option<bool>,
+    mako_mode: Option<bool>,
+}
+
+/// Turns on Do-Not-Disturb while blocking is in place and puts back
+/// whatever was set before.
+///
+/// dunst is paused outright, which hides Brisk's reminders too unless the
+/// user adds a rule with `appname = Brisk` and `override_pause_level = 100`.
+pub struct Dnd {
+    runner: Box<dyn CommandRunner>,
+    inhibitor: Box<dyn Inhibitor>,
+    /// The entries of `$XDG_CURRENT_DESKTOP`, such as `ubuntu` and `GNOME`.
+    desktops: Vec<String>,
+    path: PathBuf,
+    /// Set while Do-Not-Disturb is on.
+    previous: Mutex<Option<Previous>>,
+    /// KDE Plasma drops an inhibition when the process holding it exits,
+    /// so it is kept in memory only.
+    kde_cookie: Mutex<Option<u32>>,
+}
+
+impl Dnd {
+    pub fn load() -> Self {
+        Self::new(
+            Box::new(SystemRunner),
+            Box::new(NotificationInhibitor),
+            current_desktops(),
+            xdg::app_data_dir().join("dnd.json"),
+        )
+    }
+
+    pub fn new(
+        runner: Box<dyn CommandRunner>,
+        inhibitor: Box<dyn Inhibitor>,
+        desktops: Vec<String>,
+        path: PathBuf,
+    ) -> Self {
+        let previous = store::read(&path);
+
+        Self {
+            runner,
+            inhibitor,
+            desktops,
+            path,
+            previous: Mutex::new(previous),
+            kde_cookie: Mutex::new(None),
+        }
+    }
+
+    fn desktop_is(&self, name: &str) -> bool {
+        self.desktops
+            .iter()
+            .any(|desktop| desktop.eq_ignore_ascii_case(name))
+    }
+
+    /// Switches on Do-Not-Disturb on every desktop that is detected. The
+    /// desktops that fail are reported together; the others stay on.
+    pub fn enable(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        let mut previous = self.previous.lock().unwrap();
+        if previous.is_none() {
+            let mut state = Previous::default();
+
+            if self.desktop_is("GNOME") {
+                match self.enable_gnome() {
+                    Ok(shown) => state.show_banners = Some(shown),
+                    Err(e) => errors.push(e),
+                }
+            }
+            if let Ok(paused) = self.runner.run("dunstctl", &["is-paused"]) {
+                match self.runner.run("dunstctl", &["set-paused", "true"]) {
+                    Ok(_) => state.dunst_paused = Some(paused == "true"),
+                    Err(e) => errors.push(e),
+                }
+            }
+            if let Ok(modes) = self.runner.run("makoctl", &["mode"]) {
+                let active = modes.lines().any(|mode| mode.trim() == MAKO_MODE);
+                match self.runner.run("makoctl", &["mode", "-a", MAKO_MODE]) {
+                    Ok(_) => state.mako_mode = Some(active),
+                    Err(e) => errors.push(e),
+                }
+            }
+
+            if let Err(e) = self.save(&state) {
+                errors.push(e);
+            }
+            *previous = Some(state);
+        }
+
+        let mut kde_cookie = self.kde_cookie.lock().unwrap();
+        if self.desktop_is("KDE") && kde_cookie.is_none() {
+            match self.inhibitor.inhibit("Focus session") {
+                Ok(cookie) => *kde_cookie = Some(cookie),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        // Brisk's own notifications are sent as critical from now on, which
+        // GNOME still shows
+        notifications::set_do_not_disturb(true);
+        into_result(errors)
+    }
+
+    /// Returns whether banners were shown before turning them off.
+    fn enable_gnome(&self) -> Result<bool, String> {
+        let shown = self
+            .runner
+            .run("gsettings", &["get", GNOME_SCHEMA, GNOME_KEY])?;
+        self.runner
+            .run("gsettings", &["set", GNOME_SCHEMA, GNOME_KEY, "false"])?;
+        Ok(shown == "true")
+    }
+
+    /// Puts every desktop back the way it was before [`Dnd::enable`].
+    pub fn restore(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+
+        if let Some(previous) = self.previous.lock().unwrap().take() {
+            if let Some(shown) = previous.show_banners {
+                let value = shown.to_string();
+                let result = self
+                    .runner
+                    .run("gsettings", &["set", GNOME_SCHEMA, GNOME_KEY, &value]);
+                errors.extend(result.err());
+            }
+            if let Some(paused) = previous.dunst_paused {
+                let value = paused.to_string();
+                let result = self.runner.run("dunstctl", &["set-paused", &value]);
+                errors.extend(result.err());
+            }
+            if previous.mako_mode == Some(false) {
+                let result = self.runner.run("makoctl", &["mode", "-r", MAKO_MODE]);
+                errors.extend(result.err());
+            }
+
+            if self.path.exists() {
+                if let Err(e) = fs::remove_file(&self.path) {
+                    errors.push(format!("Failed to remove {:?}: {}", self.path, e));
+                }
+            }
+        }
+
+        if let Some(cookie) = self.kde_cookie.lock().unwrap().take() {
+            errors.extend(self.inhibitor.uninhibit(cookie).err());
+        }
+
+        notifications::set_do_not_disturb(false);
+        into_result(errors)
+    }
+
+    fn save(&self, previous: &Previous) -> Result<(), String> {
+        store::write(&self.path, previous)
+    }
+}
+
+/// `$XDG_CURRENT_DESKTOP` is a colon-separated list such as `ubuntu:GNOME`.
+fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|desktop| !desktop.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn into_result(errors: Vec<String>) -> Result<(), String> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    /// Stands in for the desktop tools. Programs that are not installed
+    /// fail to run; installed ones answer with canned output, or nothing.
+    #[derive(Clone, Default)]
+    struct FakeRunner {
+        installed: Vec<&'static str>,
+        outputs: HashMap<String, String>,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeRunner {
+        fn with(mut self, program: &'static str) -> Self {
+            self.installed.push(program);
+            self
+        }
+
+        fn answer(mut self, command: &str, output: &str) -> Self {
+            self.outputs.insert(command.to_string(), output.to_string());
+            self
+        }
+
+        fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        fn ran(&self, command: &str) -> bool {
+            self.calls().iter().any(|call| call == command)
+        }
+    }
+
+    impl CommandRunner for FakeRunner {
+        fn run(&self, program: &str, args: &[&str]) -> Result<String, String> {
+            let command = [&[program], args].concat().join(" ");
+            self.calls.lock().unwrap().push(command.clone());
+
+            if !self.installed.contains(&program) {
+                return Err(format!("Failed to run {}: not found", program));
+            }
+            Ok(self.outputs.get(&command).cloned().unwrap_or_default())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct FakeInhibitor {
+        held: Arc<Mutex<Vec<u32>>>,
+    }
+
+    impl Inhibitor for FakeInhibitor {
+        fn inhibit(&self, _reason: &str) -> Result<u32, String> {
+            let mut held = self.held.lock().unwrap();
+            let cookie = held.len() as u32 + 1;
+            held.push(cookie);
+            Ok(cookie)
+        }
+
+        fn uninhibit(&self, cookie: u32) -> Result<(), String> {
+            self.held.lock().unwrap().retain(|held| *held != cookie);
+            Ok(())
+        }
+    }
+
+    const GNOME_GET: &str = "gsettings get org.gnome.desktop.notifications show-banners";
+    const GNOME_OFF: &str = "gsettings set org.gnome.desktop.notifications show-banners false";
+    const GNOME_ON: &str = "gsettings set org.gnome.desktop.notifications show-banners true";
+
+    fn state_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brisk-dnd-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("dnd.json")
+    }
+
+    fn dnd_with(runner: &FakeRunner, desktops: &[&str], path: &Path) -> Dnd {
+        Dnd::new(
+            Box::new(runner.clone()),
+            Box::new(FakeInhibitor::default()),
+            desktops.iter().map(|d| d.to_string()).collect(),
+            path.to_path_buf(),
+        )
+    }
+
+    #[test]
+    fn gnome_banners_are_turned_off_and_back_on() {
+        let runner = FakeRunner::default()
+            .with("gsettings")
+            .answer(GNOME_GET, "true");
+        let dnd = dnd_with(&runner, &["ubuntu", "GNOME"], &state_path("gnome"));
+
+        dnd.enable().unwrap();
+        assert!(runner.ran(GNOME_OFF));
+
+        dnd.restore().unwrap();
+        assert!(runner.ran(GNOME_ON));
+    }
+
+    #[test]
+    fn gnome_banners_that_were_off_stay_off() {
+        let runner = FakeRunner::default()
+            .with("gsettings")
+            .answer(GNOME_GET, "false");
+        let dnd = dnd_with(&runner, &["GNOME"], &state_path("gnome-off"));
+
+        dnd.enable().unwrap();
+        dnd.restore().unwrap();
+        assert!(!runner.ran(GNOME_ON));
+    }
+
+    #[test]
+    fn gnome_is_left_alone_on_other_desktops() {
+        let runner = FakeRunner::default().with("gsettings");
+        let dnd = dnd_with(&runner, &["sway"], &state_path("not-gnome"));
+
+        dnd.enable().unwrap();
+        dnd.restore().unwrap();
+        assert!(!runner
+            .calls()
+            .iter()
+            .any(|call| call.starts_with("gsettings")));
+    }
+
+    #[test]
+    fn dunst_is_paused_and_resumed() {
+        let runner = FakeRunner::default()
+            .with("dunstctl")
+            .answer("dunstctl is-paused", "false");
+        let dnd = dnd_with(&runner, &[], &state_path("dunst"));
+
+        dnd.enable().unwrap();
+        assert!(runner.ran("dunstctl set-paused true"));
+
+        dnd.restore().unwrap();
+        assert!(runner.ran("dunstctl set-paused false"));
+    }
+
+    #[test]
+    fn mako_mode_is_removed_when_brisk_added_it() {
+        let runner = FakeRunner::default()
+            .with("makoctl")
+            .answer("makoctl mode", "default");
+        let dnd = dnd_with(&runner, &[], &state_path("mako"));
+
+        dnd.enable().unwrap();
+        assert!(runner.ran("makoctl mode -a do-not-disturb"));
+
+        dnd.restore().unwrap();
+        assert!(runner.ran("makoctl mode -r do-not-disturb"));
+    }
+
+    #[test]
+    fn mako_mode_the_user_set_is_kept() {
+        let runner = FakeRunner::default()
+            .with("makoctl")
+            .answer("makoctl mode", "default\ndo-not-disturb");
+        let dnd = dnd_with(&runner, &[], &state_path("mako-on"));
+
+        dnd.enable().unwrap();
+        dnd.restore().unwrap();
+        assert!(!runner.ran("makoctl mode -r do-not-disturb"));
+    }
+
+    #[test]
+    fn kde_notifications_are_inhibited_until_restored() {
+        let inhibitor = FakeInhibitor::default();
+        let dnd = Dnd::new(
+            Box::new(FakeRunner::default()),
+            Box::new(inhibitor.clone()),
+            vec!["KDE".to_string()],
+            state_path("kde"),
+        );
+
+        dnd.enable().unwrap();
+        dnd.enable().unwrap();
+        assert_eq!(inhibitor.held.lock().unwrap().len(), 1);
+
+        dnd.restore().unwrap();
+        assert!(inhibitor.held.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn enabling_again_keeps_the_state_from_before_the_first_time() {
+        let runner = FakeRunner::default()
+            .with("dunstctl")
+            .answer("dunstctl is-paused", "false");
+        let dnd = dnd_with(&runner, &[], &state_path("twice"));
+
+        dnd.enable().unwrap();
+        dnd.enable().unwrap();
+        let checks = runner
+            .calls()
+            .iter()
+            .filter(|call| *call == "dunstctl is-paused")
+            .count();
+        assert_eq!(checks, 1);
+    }
+
+    #[test]
+    fn restart_restores_the_state_saved_before_it() {
+        let path = state_path("restart");
+        let before = FakeRunner::default()
+            .with("gsettings")
+            .with("dunstctl")
+            .answer(GNOME_GET, "true")
+            .answer("dunstctl is-paused", "false");
+        dnd_with(&before, &["GNOME"], &path).enable().unwrap();
+        assert!(path.exists());
+
+        // After the restart banners read as off and dunst as paused
+        let after = FakeRunner::default()
+            .with("gsettings")
+            .with("dunstctl")
+            .answer(GNOME_GET, "false")
+            .answer("dunstctl is-paused", "true");
+        dnd_with(&after, &["GNOME"], &path).restore().unwrap();
+
+        assert!(after.ran(GNOME_ON));
+        assert!(after.ran("dunstctl set-paused false"));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn a_failing_desktop_does_not_stop_the_others() {
+        let runner = FakeRunner::default()
+            .with("dunstctl")
+            .answer("dunstctl is-paused", "false");
+        let dnd = dnd_with(&runner, &["GNOME"], &state_path("failure"));
+
+        assert!(dnd.enable().is_err());
+        assert!(runner.ran("dunstctl set-paused true"));
+    }
+}
@@ -0,0 +1,353 @@
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+const NETWORK_MANAGER_CONF: &str = "/etc/NetworkManager/NetworkManager.conf";
+const NETWORK_MANAGER_CONF_DIR: &str = "/etc/NetworkManager/conf.d";
+
+/// A caching resolver that can hold on to answers from before a hosts file
+/// change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolver {
+    SystemdResolved,
+    Nscd,
+    Dnsmasq,
+    NetworkManagerDnsmasq,
+}
+
+impl Resolver {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Resolver::SystemdResolved => "systemd-resolved",
+            Resolver::Nscd => "nscd",
+            Resolver::Dnsmasq => "dnsmasq",
+            Resolver::NetworkManagerDnsmasq => "NetworkManager dnsmasq",
+        }
+    }
+}
+
+/// What detection looks at, gathered up front so the decision itself does
+/// not depend on the machine it runs on.
+#[derive(Debug, Clone, Default)]
+pub struct SystemState {
+    pub resolv_conf_target: Option<String>,
+    pub nameservers: Vec<String>,
+    pub active_services: Vec<String>,
+    pub network_manager_dns: Option<String>,
+}
+
+impl SystemState {
+    pub fn read() -> Self {
+        let resolv_conf_target = fs::read_link(RESOLV_CONF)
+            .ok()
+            .map(|target| target.to_string_lossy().to_string());
+
+        let nameservers = fs::read_to_string(RESOLV_CONF)
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("nameserver"))
+            .map(|server| server.trim().to_string())
+            .collect();
+
+        let active_services = ["systemd-resolved", "nscd", "dnsmasq"]
+            .iter()
+            .filter(|service| is_service_active(service))
+            .map(|service| service.to_string())
+            .collect();
+
+        Self {
+            resolv_conf_target,
+            nameservers,
+            active_services,
+            network_manager_dns: network_manager_dns_mode(),
+        }
+    }
+}
+
+/// Picks the caches that need flushing. nscd caches hosts lookups in front
+/// of whichever DNS stack is in use, so it can appear alongside the others.
+pub fn detect(state: &SystemState) -> Vec<Resolver> {
+    let service_active = |name: &str| state.active_services.iter().any(|s| s == name);
+    let uses_nameserver =
+        |prefix: &str| state.nameservers.iter().any(|ns| ns.starts_with(prefix));
+
+    let mut resolvers = Vec::new();
+
+    let resolved_stub = state
+        .resolv_conf_target
+        .as_deref()
+        .is_some_and(|target| target.contains("systemd/resolve"))
+        || uses_nameserver("127.0.0.53");
+
+    if service_active("systemd-resolved") && resolved_stub {
+        resolvers.push(Resolver::SystemdResolved);
+    } else if state.network_manager_dns.as_deref() == Some("dnsmasq")
+        && uses_nameserver("127.0.")
+    {
+        resolvers.push(Resolver::NetworkManagerDnsmasq);
+    } else if service_active("dnsmasq") && uses_nameserver("127.") {
+        resolvers.push(Resolver::Dnsmasq);
+    } else if service_active("systemd-resolved") {
+        // resolved can still cache for apps that talk to it over D-Bus or
+        // nss-resolve even when resolv.conf points elsewhere
+        resolvers.push(Resolver::SystemdResolved);
+    }
+
+    if service_active("nscd") {
+        resolvers.push(Resolver::Nscd);
+    }
+
+    resolvers
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FlushOutcome {
+    pub resolver: Resolver,
+    pub flushed: bool,
+    pub command: Option<String>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FlushReport {
+    pub outcomes: Vec<FlushOutcome>,
+}
+
+impl FlushReport {
+    pub fn all_flushed(&self) -> bool {
+        self.outcomes.iter().all(|o| o.flushed)
+    }
+
+    /// One line for command results, e.g. "DNS cache flushed (systemd-resolved)".
+    pub fn summary(&self) -> String {
+        if self.outcomes.is_empty() {
+            return "No DNS cache detected".to_string();
+        }
+
+        let names = |flushed: bool| {
+            self.outcomes
+                .iter()
+                .filter(|o| o.flushed == flushed)
+                .map(|o| o.resolver.name())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        if self.all_flushed() {
+            format!("DNS cache flushed ({})", names(true))
+        } else {
+            format!("Failed to flush DNS cache ({})", names(false))
+        }
+    }
+}
+
+/// Flushes the caches of the resolvers in use on this machine.
+pub fn flush_caches() -> FlushReport {
+    let resolvers = detect(&SystemState::read());
+    FlushReport {
+        outcomes: resolvers.into_iter().map(flush).collect(),
+    }
+}
+
+fn flush(resolver: Resolver) -> FlushOutcome {
+    let attempts: &[&[&str]] = match resolver {
+        Resolver::SystemdResolved => &[
+            &["resolvectl", "flush-caches"],
+            &["systemd-resolve", "--flush-caches"],
+        ],
+        Resolver::Nscd => &[&["nscd", "-i", "hosts"]],
+        // SIGHUP makes dnsmasq drop its cache and reread /etc/hosts; the
+        // NetworkManager instance is not a systemd unit, so signal by name
+        Resolver::Dnsmasq | Resolver::NetworkManagerDnsmasq => {
+            &[&["pkill", "-HUP", "-x", "dnsmasq"]]
+        }
+    };
+
+    let mut last_error = None;
+    for args in attempts {
+        match run_privileged(args) {
+            Ok(()) => {
+                return FlushOutcome {
+                    resolver,
+                    flushed: true,
+                    command: Some(args.join(" ")),
+                    error: None,
+                }
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    FlushOutcome {
+        resolver,
+        flushed: false,
+        command: None,
+        error: last_error,
+    }
+}
+
+/// Tries the command as the current user first, which polkit allows for
+/// `resolvectl` on most desktops, then through passwordless sudo.
+fn run_privileged(args: &[&str]) -> Result<(), String> {
+    let (program, rest) = args.split_first().ok_or("Empty command")?;
+
+    if Command::new(program)
+        .args(rest)
+        .output()
+        .is_ok_and(|o| o.status.success())
+    {
+        return Ok(());
+    }
+
+    let output = Command::new("sudo")
+        .arg("-n")
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+fn is_service_active(service: &str) -> bool {
+    Command::new("systemctl")
+        .args(["is-active", "--quiet", service])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|s| s.success())
+}
+
+/// The `dns=` setting of the `[main]` section, with conf.d files overriding
+/// the main file in name order.
+fn network_manager_dns_mode() -> Option<String> {
+    let mut files = vec![Path::new(NETWORK_MANAGER_CONF).to_path_buf()];
+    if let Ok(entries) = fs::read_dir(NETWORK_MANAGER_CONF_DIR) {
+        let mut conf_d: Vec<_> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().and_then(|s| s.to_str()) == Some("conf"))
+            .collect();
+        conf_d.sort();
+        files.extend(conf_d);
+    }
+
+    let mut mode = None;
+    for file in files {
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+
+        let mut in_main = false;
+        for line in content.lines().map(str::trim) {
+            if line.starts_with('[') {
+                in_main = line == "[main]";
+            } else if in_main {
+                if let Some(value) = line.strip_prefix("dns=") {
+                    mode = Some(value.trim().to_string());
+                }
+            }
+        }
+    }
+
+    mode
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(
+        target: Option<&str>,
+        nameservers: &[&str],
+        services: &[&str],
+        network_manager_dns: Option<&str>,
+    ) -> SystemState {
+        SystemState {
+            resolv_conf_target: target.map(str::to_string),
+            nameservers: nameservers.iter().map(|s| s.to_string()).collect(),
+            active_services: services.iter().map(|s| s.to_string()).collect(),
+            network_manager_dns: network_manager_dns.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn detects_the_caches_in_use() {
+        use Resolver::*;
+
+        let stub = Some("../run/systemd/resolve/stub-resolv.conf");
+        let cases: &[(&str, SystemState, &[Resolver])] = &[
+            (
+                "resolved stub through the symlink",
+                state(stub, &["127.0.0.53"], &["systemd-resolved"], None),
+                &[SystemdResolved],
+            ),
+            (
+                "resolved stub named in a plain resolv.conf",
+                state(None, &["127.0.0.53"], &["systemd-resolved"], None),
+                &[SystemdResolved],
+            ),
+            (
+                "NetworkManager's dnsmasq",
+                state(None, &["127.0.1.1"], &[], Some("dnsmasq")),
+                &[NetworkManagerDnsmasq],
+            ),
+            (
+                "NetworkManager's dnsmasq with resolved running elsewhere",
+                state(None, &["127.0.1.1"], &["systemd-resolved"], Some("dnsmasq")),
+                &[NetworkManagerDnsmasq],
+            ),
+            (
+                "standalone dnsmasq",
+                state(None, &["127.0.0.1"], &["dnsmasq"], None),
+                &[Dnsmasq],
+            ),
+            (
+                "dnsmasq running but not in resolv.conf",
+                state(None, &["192.168.1.1"], &["dnsmasq"], None),
+                &[],
+            ),
+            (
+                "resolved running but not in resolv.conf",
+                state(None, &["192.168.1.1"], &["systemd-resolved"], None),
+                &[SystemdResolved],
+            ),
+            (
+                "nscd alone",
+                state(None, &["192.168.1.1"], &["nscd"], None),
+                &[Nscd],
+            ),
+            (
+                "nscd in front of resolved",
+                state(stub, &["127.0.0.53"], &["systemd-resolved", "nscd"], None),
+                &[SystemdResolved, Nscd],
+            ),
+            (
+                "nscd in front of dnsmasq",
+                state(None, &["127.0.0.1"], &["dnsmasq", "nscd"], None),
+                &[Dnsmasq, Nscd],
+            ),
+            (
+                "no cache",
+                state(None, &["192.168.1.1"], &[], Some("default")),
+                &[],
+            ),
+            ("nothing known", SystemState::default(), &[]),
+        ];
+
+        for (name, state, expected) in cases {
+            assert_eq!(detect(state), *expected, "{}", name);
+        }
+    }
+}
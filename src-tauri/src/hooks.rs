@@ -0,0 +1,507 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Listener};
+
+use crate::notifications::{RateLimiter, RATE_LIMIT, RATE_LIMIT_WINDOW};
+use crate::session::{ActiveSession, SessionState};
+use crate::store;
+use crate::xdg;
+
+const DEFAULT_TIMEOUT: u64 = 30;
+const WAIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Block attempts by name that ran hooks recently, so an app relaunching
+/// in a loop does not start a process per attempt.
+static BLOCK_ATTEMPTS: OnceLock<RateLimiter<String>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    SessionStart,
+    SessionEnd,
+    Pause,
+    BlockAttempt,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::SessionStart => "session_start",
+            HookEvent::SessionEnd => "session_end",
+            HookEvent::Pause => "pause",
+            HookEvent::BlockAttempt => "block_attempt",
+        }
+    }
+}
+
+/// A command run through `sh -c` when `event` happens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hook {
+    pub event: HookEvent,
+    pub command: String,
+    /// Seconds the command may run before it is killed.
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+}
+
+fn default_timeout() -> u64 {
+    DEFAULT_TIMEOUT
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockedKind {
+    App,
+    Site,
+}
+
+/// What a block attempt was caught trying to open.
+#[derive(Debug, Clone, Serialize)]
+pub struct Blocked {
+    pub kind: BlockedKind,
+    pub name: String,
+}
+
+/// Written as JSON to each hook's stdin.
+#[derive(Debug, Serialize)]
+struct HookInput<'a> {
+    event: HookEvent,
+    timestamp: i64,
+    session: Option<&'a ActiveSession>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    blocked: Option<&'a Blocked>,
+}
+
+/// Hooks configured in `hooks.json` under the config home. Events read the
+/// file afresh rather than going through the app's store, so hand edits
+/// apply without restarting Brisk and the CLI runs the same hooks.
+pub struct HookStore {
+    path: PathBuf,
+    hooks: Mutex<Vec<Hook>>,
+}
+
+impl HookStore {
+    pub fn load() -> Self {
+        Self::load_from(xdg::config_home().join("brisk").join("hooks.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let hooks = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            hooks: Mutex::new(hooks),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Hook> {
+        self.hooks.lock().unwrap().clone()
+    }
+
+    pub fn set(&self, hooks: Vec<Hook>) -> Result<Vec<Hook>, String> {
+        let hooks: Vec<Hook> = hooks
+            .into_iter()
+            .map(|hook| Hook {
+                command: hook.command.trim().to_string(),
+                ..hook
+            })
+            .collect();
+
+        if hooks.iter().any(|hook| hook.command.is_empty()) {
+            return Err("Hook command cannot be empty".to_string());
+        }
+        if hooks.iter().any(|hook| hook.timeout == 0) {
+            return Err("Hook timeout must be greater than zero".to_string());
+        }
+
+        let mut current = self.hooks.lock().unwrap();
+        store::write(&self.path, &hooks)?;
+        *current = hooks.clone();
+        Ok(hooks)
+    }
+}
+
+fn log_path() -> PathBuf {
+    xdg::app_data_dir().join("hooks.log")
+}
+
+/// Runs the hooks of the session events the app emits.
+pub fn listen(app: &AppHandle) {
+    for (name, event) in [
+        ("session-started", HookEvent::SessionStart),
+        ("session-ended", HookEvent::SessionEnd),
+        ("session-paused", HookEvent::Pause),
+    ] {
+        app.listen_any(name, move |payload| {
+            let session = serde_json::from_str::<ActiveSession>(payload.payload()).ok();
+            spawn(event, session, None);
+        });
+    }
+}
+
+/// Runs the block attempt hooks for the active session in the background.
+pub fn spawn_block_attempt(kind: BlockedKind, name: &str) {
+    // Blocked apps are caught again on every relaunch, so skip reading the
+    // session when no hook would get it
+    let wanted = HookStore::load()
+        .list()
+        .iter()
+        .any(|hook| hook.event == HookEvent::BlockAttempt);
+    if !wanted {
+        return;
+    }
+    let limiter = BLOCK_ATTEMPTS.get_or_init(|| RateLimiter::new(RATE_LIMIT, RATE_LIMIT_WINDOW));
+    if !limiter.allow(name.to_string()) {
+        return;
+    }
+
+    let blocked = Blocked {
+        kind,
+        name: name.to_string(),
+    };
+    spawn(
+        HookEvent::BlockAttempt,
+        SessionState::load().current(),
+        Some(blocked),
+    );
+}
+
+/// Runs the hooks for `event` in the background.
+pub fn spawn(event: HookEvent, session: Option<ActiveSession>, blocked: Option<Blocked>) {
+    thread::spawn(move || run(event, session.as_ref(), blocked.as_ref()));
+}
+
+/// Runs the hooks for `event` side by side and waits for all of them.
+pub fn run(event: HookEvent, session: Option<&ActiveSession>, blocked: Option<&Blocked>) {
+    let hooks: Vec<Hook> = HookStore::load()
+        .list()
+        .into_iter()
+        .filter(|hook| hook.event == event)
+        .collect();
+    if hooks.is_empty() {
+        return;
+    }
+
+    let input = HookInput {
+        event,
+        timestamp: Utc::now().timestamp(),
+        session,
+        blocked,
+    };
+    let json = match serde_json::to_string(&input) {
+        Ok(json) => json,
+        Err(e) => {
+            eprintln!("Failed to serialize hook input: {}", e);
+            return;
+        }
+    };
+    let env = environment(&input);
+
+    thread::scope(|scope| {
+        for hook in &hooks {
+            let json = &json;
+            let env = &env;
+            scope.spawn(move || {
+                let started = Instant::now();
+                let outcome = execute(hook, json, env);
+                log(&log_line(event, hook, &outcome, started.elapsed()));
+            });
+        }
+    });
+}
+
+/// Session details for hooks that would rather not parse JSON.
+fn environment(input: &HookInput) -> Vec<(&'static str, String)> {
+    let mut env = vec![
+        ("BRISK_EVENT", input.event.name().to_string()),
+        ("BRISK_TIMESTAMP", input.timestamp.to_string()),
+    ];
+
+    if let Some(session) = input.session {
+        env.push(("BRISK_GOAL", session.goal.clone()));
+        env.push(("BRISK_STARTED_AT", session.started_at.to_string()));
+        env.push(("BRISK_ENDS_AT", session.ends_at.to_string()));
+        env.push((
+            "BRISK_REMAINING",
+            (session.ends_at - input.timestamp).max(0).to_string(),
+        ));
+        if let Some(profile) = &session.profile {
+            env.push(("BRISK_PROFILE", profile.clone()));
+        }
+    }
+    if let Some(blocked) = input.blocked {
+        let kind = match blocked.kind {
+            BlockedKind::App => "app",
+            BlockedKind::Site => "site",
+        };
+        env.push(("BRISK_BLOCKED_KIND", kind.to_string()));
+        env.push(("BRISK_BLOCKED_NAME", blocked.name.clone()));
+    }
+
+    env
+}
+
+enum Outcome {
+    Exited(ExitStatus),
+    TimedOut,
+    Failed(String),
+}
+
+fn execute(hook: &Hook, input: &str, env: &[(&str, String)]) -> Outcome {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&hook.command)
+        .envs(env.iter().cloned())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Its own process group, so a timeout kills whatever it started too
+        .process_group(0)
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return Outcome::Failed(e.to_string()),
+    };
+
+    // Written from another thread, as a hook that never reads stdin would
+    // otherwise block the write past its timeout once the pipe is full. A
+    // hook that ignores stdin closes the pipe early; that is not an error.
+    if let Some(mut stdin) = child.stdin.take() {
+        let input = input.to_string();
+        thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+
+    wait(&mut child, Duration::from_secs(hook.timeout))
+}
+
+fn wait(child: &mut Child, timeout: Duration) -> Outcome {
+    let deadline = Instant::now() + timeout;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return Outcome::Exited(status),
+            Ok(None) if Instant::now() >= deadline => {
+                let _ = Command::new("kill")
+                    .args(["-KILL", "--", &format!("-{}", child.id())])
+                    .output();
+                let _ = child.kill();
+                let _ = child.wait();
+                return Outcome::TimedOut;
+            }
+            Ok(None) => thread::sleep(WAIT_INTERVAL),
+            Err(e) => return Outcome::Failed(e.to_string()),
+        }
+    }
+}
+
+/// How a hook run went, as a line of the hook log.
+fn log_line(event: HookEvent, hook: &Hook, outcome: &Outcome, elapsed: Duration) -> String {
+    let result = match outcome {
+        Outcome::Exited(status) => match status.code() {
+            Some(code) => format!("exited with status {}", code),
+            None => "was killed by a signal".to_string(),
+        },
+        Outcome::TimedOut => format!("timed out after {}s and was killed", hook.timeout),
+        Outcome::Failed(e) => format!("failed to run: {}", e),
+    };
+    format!(
+        "{} {} `{}` {} ({} ms)",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        event.name(),
+        hook.command,
+        result,
+        elapsed.as_millis()
+    )
+}
+
+fn log(line: &str) {
+    let path = log_path();
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = written {
+        eprintln!("Failed to write hook log {:?}: {}", path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+
+    use crate::profiles::EnforcementMode;
+    use crate::reminders::ReminderSettings;
+
+    fn temp_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brisk-hooks-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn hook(command: &str, timeout: u64) -> Hook {
+        Hook {
+            event: HookEvent::SessionStart,
+            command: command.to_string(),
+            timeout,
+        }
+    }
+
+    fn session() -> ActiveSession {
+        ActiveSession {
+            goal: "Write the report".to_string(),
+            started_at: 1000,
+            ends_at: 2500,
+            profile: Some("writing".to_string()),
+            enforcement: EnforcementMode::Standard,
+            schedule: None,
+            record: "session.json".into(),
+            blocked_things: Vec::new(),
+            blocked_apps: Vec::new(),
+            pause: None,
+            paused_for: 0,
+            pause_budget: 0,
+            unlocked: false,
+            reminders: ReminderSettings::default(),
+        }
+    }
+
+    /// Whether `pid` is still running, as opposed to gone or a zombie
+    /// nobody has reaped yet.
+    fn is_running(pid: &str) -> bool {
+        fs::read_to_string(format!("/proc/{}/stat", pid)).is_ok_and(|stat| !stat.contains(") Z "))
+    }
+
+    #[test]
+    fn exit_status_is_reported() {
+        let outcome = execute(&hook("exit 3", 5), "", &[]);
+
+        assert!(matches!(outcome, Outcome::Exited(status) if status.code() == Some(3)));
+    }
+
+    #[test]
+    fn hooks_get_the_payload_on_stdin_and_in_the_environment() {
+        let out = temp_dir("payload").join("out");
+        let session = session();
+        let input = HookInput {
+            event: HookEvent::SessionStart,
+            timestamp: 1900,
+            session: Some(&session),
+            blocked: None,
+        };
+        let json = serde_json::to_string(&input).unwrap();
+        let command = format!(
+            "{{ printf '%s\\n' \"$BRISK_EVENT\" \"$BRISK_GOAL\" \"$BRISK_REMAINING\" \
+             \"$BRISK_PROFILE\"; cat; }} > '{}'",
+            out.display()
+        );
+
+        let outcome = execute(&hook(&command, 5), &json, &environment(&input));
+
+        assert!(matches!(outcome, Outcome::Exited(status) if status.success()));
+        assert_eq!(
+            fs::read_to_string(&out).unwrap(),
+            format!("session_start\nWrite the report\n600\nwriting\n{}", json)
+        );
+    }
+
+    #[test]
+    fn block_attempts_name_what_was_blocked() {
+        let blocked = Blocked {
+            kind: BlockedKind::Site,
+            name: "example.com".to_string(),
+        };
+        let input = HookInput {
+            event: HookEvent::BlockAttempt,
+            timestamp: 1900,
+            session: None,
+            blocked: Some(&blocked),
+        };
+
+        let env = environment(&input);
+
+        assert!(env.contains(&("BRISK_EVENT", "block_attempt".to_string())));
+        assert!(env.contains(&("BRISK_BLOCKED_KIND", "site".to_string())));
+        assert!(env.contains(&("BRISK_BLOCKED_NAME", "example.com".to_string())));
+        assert!(!env.iter().any(|(name, _)| *name == "BRISK_GOAL"));
+    }
+
+    #[test]
+    fn hooks_that_ignore_stdin_do_not_block() {
+        let input = "x".repeat(1 << 20);
+
+        let outcome = execute(&hook("true", 5), &input, &[]);
+
+        assert!(matches!(outcome, Outcome::Exited(status) if status.success()));
+    }
+
+    #[test]
+    fn timeout_kills_the_whole_process_group() {
+        let pid_file = temp_dir("timeout").join("pid");
+        let command = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+        let started = Instant::now();
+
+        let outcome = execute(&hook(&command, 1), "", &[]);
+
+        assert!(matches!(outcome, Outcome::TimedOut));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        let pid = fs::read_to_string(&pid_file).unwrap();
+        let pid = pid.trim();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while is_running(pid) && Instant::now() < deadline {
+            thread::sleep(WAIT_INTERVAL);
+        }
+        assert!(!is_running(pid), "sleep {} survived the timeout", pid);
+    }
+
+    #[test]
+    fn log_lines_describe_the_outcome() {
+        let hook = hook("notify-send hi", 7);
+        let line = |outcome: Outcome| {
+            log_line(
+                HookEvent::SessionEnd,
+                &hook,
+                &outcome,
+                Duration::from_millis(42),
+            )
+        };
+
+        assert!(line(Outcome::Exited(ExitStatus::from_raw(3 << 8)))
+            .ends_with(" session_end `notify-send hi` exited with status 3 (42 ms)"));
+        assert!(line(Outcome::Exited(ExitStatus::from_raw(9))).contains("was killed by a signal"));
+        assert!(line(Outcome::TimedOut).contains("timed out after 7s and was killed"));
+        assert!(line(Outcome::Failed("No such file".to_string()))
+            .contains("failed to run: No such file"));
+    }
+
+    #[test]
+    fn store_trims_validates_and_persists_hooks() {
+        let path = temp_dir("store").join("hooks.json");
+        let store = HookStore::load_from(path.clone());
+
+        assert!(store.set(vec![hook("  ", 5)]).is_err());
+        assert!(store.set(vec![hook("true", 0)]).is_err());
+        assert!(!path.exists());
+
+        let saved = store.set(vec![hook("  notify-send hi ", 5)]).unwrap();
+
+        assert_eq!(saved, [hook("notify-send hi", 5)]);
+        assert_eq!(store.list(), saved);
+        assert_eq!(HookStore::load_from(path).list(), saved);
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::block::{self, extract_domain, SiteRule, HOSTS_FILE};
+use crate::commands;
+use crate::notifications::{self, Kind, Notification};
+use crate::profiles::EnforcementMode;
+use crate::session::SessionState;
+
+/// Editors and other tools often write the file in several steps.
+const CHECK_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Block lines that went missing from the hosts file during a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TamperEvent {
+    pub detected_at: i64,
+    pub missing: Vec<String>,
+    /// Whether the block lines could be written back.
+    pub restored: bool,
+}
+
+/// Watches the hosts file while sites are blocked and puts back block
+/// lines that are removed behind Brisk's back.
+pub struct HostsGuard {
+    /// Sites the hosts file should currently block, empty while blocking
+    /// is lifted.
+    expected: Arc<Mutex<Vec<String>>>,
+    /// Held while lifting and while restoring, so a restore cannot land
+    /// after blocking has been lifted.
+    writing: Arc<Mutex<()>>,
+    /// Only runs while sites are expected to be blocked.
+    watcher: Mutex<Option<RecommendedWatcher>>,
+}
+
+impl HostsGuard {
+    pub fn new() -> Self {
+        Self {
+            expected: Arc::new(Mutex::new(Vec::new())),
+            writing: Arc::new(Mutex::new(())),
+            watcher: Mutex::new(None),
+        }
+    }
+
+    /// Starts guarding the block lines of `sites`, which have just been
+    /// written to the hosts file.
+    pub fn expect(&self, app: &AppHandle, sites: &[String]) {
+        *self.expected.lock().unwrap() = sites.to_vec();
+
+        let mut watcher = self.watcher.lock().unwrap();
+        if sites.is_empty() {
+            *watcher = None;
+        } else if watcher.is_none() {
+            match self.watch(app.clone()) {
+                Ok(started) => *watcher = Some(started),
+                Err(e) => eprintln!("Changes to the hosts file will not be detected: {}", e),
+            }
+        }
+    }
+
+    /// Stops guarding and lifts blocking through `unblock`. No restore runs
+    /// in the meantime, so the removal is neither mistaken for tampering nor
+    /// undone by a check that was already under way.
+    pub fn lift<T>(&self, unblock: impl FnOnce() -> T) -> T {
+        let _writing = self.writing.lock().unwrap();
+        self.expected.lock().unwrap().clear();
+        // Dropping the watcher also ends its check thread
+        *self.watcher.lock().unwrap() = None;
+        unblock()
+    }
+
+    fn watch(&self, app: AppHandle) -> Result<RecommendedWatcher, String> {
+        let (tx, rx) = mpsc::channel();
+
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                // The file is usually replaced rather than written in place, so
+                // its directory is watched and other files are ignored
+                if event.is_ok_and(|e| {
+                    !e.kind.is_access() && e.paths.iter().any(|p| p == Path::new(HOSTS_FILE))
+                }) {
+                    let _ = tx.send(());
+                }
+            })
+            .map_err(|e| format!("Failed to create hosts file watcher: {}", e))?;
+
+        let dir = Path::new(HOSTS_FILE).parent().unwrap_or(Path::new("/"));
+        watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .map_err(|e| format!("Failed to watch {:?}: {}", dir, e))?;
+
+        let expected = Arc::clone(&self.expected);
+        let writing = Arc::clone(&self.writing);
+        thread::spawn(move || {
+            while rx.recv().is_ok() {
+                while rx.recv_timeout(CHECK_DEBOUNCE).is_ok() {}
+
+                let sites = expected.lock().unwrap().clone();
+                if !sites.is_empty() {
+                    check(&app, &expected, &writing, &sites);
+                }
+            }
+        });
+
+        Ok(watcher)
+    }
+}
+
+/// Domains of `sites` that have no block line in `hosts_content`, e.g.
+/// because the file was edited by hand during a session.
+fn missing_blocks(hosts_content: &str, sites: &[String]) -> Vec<String> {
+    let blocked: HashSet<&str> = hosts_content
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with("0.0.0.0"))
+        .flat_map(|line| line.split_whitespace().skip(1))
+        .collect();
+
+    let mut seen = HashSet::new();
+    sites
+        .iter()
+        .filter(|s| SiteRule::parse(s).is_some_and(|rule| rule.path.is_none()))
+        .map(|s| extract_domain(s))
+        .filter(|d| !d.is_empty() && seen.insert(d.clone()))
+        .filter(|d| !blocked.contains(d.as_str()))
+        .collect()
+}
+
+/// Restores the block lines of `sites` if any are missing, unless blocking
+/// is lifted or changed before the restore gets to write.
+fn check(app: &AppHandle, expected: &Mutex<Vec<String>>, writing: &Mutex<()>, sites: &[String]) {
+    let missing = match fs::read_to_string(HOSTS_FILE) {
+        Ok(content) => missing_blocks(&content, sites),
+        Err(e) => {
+            eprintln!("Failed to check hosts file: {}", e);
+            return;
+        }
+    };
+    if missing.is_empty() {
+        return;
+    }
+
+    // Blocking is only ever expected while a session is running unpaused,
+    // but do not write the hosts file on the strength of that alone
+    let Some(session) = app
+        .state::<SessionState>()
+        .current()
+        .filter(|s| s.pause.is_none())
+    else {
+        return;
+    };
+
+    let restored = {
+        let _writing = writing.lock().unwrap();
+        if *expected.lock().unwrap() != sites {
+            return;
+        }
+
+        eprintln!(
+            "Hosts file was modified, block lines missing for {:?}",
+            missing
+        );
+        match block::block_sites(sites) {
+            Ok(_) => true,
+            Err(e) => {
+                eprintln!("Failed to restore blocked sites: {}", e);
+                false
+            }
+        }
+    };
+
+    let event = TamperEvent {
+        detected_at: Utc::now().timestamp(),
+        missing,
+        restored,
+    };
+    match session.enforcement {
+        // The session could be ended instead, so editing the hosts file is
+        // most likely another tool's doing; putting the lines back is enough
+        EnforcementMode::Standard => {}
+        // Strict mode exists to hold the user to the session, so getting
+        // around it is reported to them
+        EnforcementMode::Strict => {
+            let body = format!(
+                "Blocking of {} was removed from {} and {}",
+                event.missing.join(", "),
+                HOSTS_FILE,
+                if event.restored {
+                    "has been restored"
+                } else {
+                    "could not be restored"
+                }
+            );
+            notifications::notify(Notification {
+                kind: Kind::Blocked,
+                icon: "dialog-warning",
+                summary: "Strict mode tampering detected",
+                body: &body,
+                actions: &[],
+            });
+        }
+    }
+    if let Err(e) = commands::record_tamper_event(app, event.clone()) {
+        eprintln!("Failed to record hosts file tampering: {}", e);
+    }
+    let _ = app.emit("hosts-tampered", &event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTS: &str = "\
+127.0.0.1 localhost
+::1 localhost
+# Focus app blocked sites
+0.0.0.0 example.com
+::1 example.com
+0.0.0.0 www.example.com
+::1 www.example.com
+0.0.0.0 news.example.org
+::1 news.example.org
+";
+
+    fn sites(sites: &[&str]) -> Vec<String> {
+        sites.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn nothing_is_missing_while_every_block_line_is_there() {
+        let sites = sites(&["example.com", "https://news.example.org/"]);
+
+        assert!(missing_blocks(HOSTS, &sites).is_empty());
+    }
+
+    #[test]
+    fn sites_without_a_block_line_are_missing() {
+        let sites = sites(&["example.com", "news.example.org", "video.example.net"]);
+
+        assert_eq!(missing_blocks(HOSTS, &sites), ["video.example.net"]);
+    }
+
+    #[test]
+    fn removed_and_commented_out_lines_count_as_missing() {
+        let edited = HOSTS
+            .replace("0.0.0.0 example.com\n", "# 0.0.0.0 example.com\n")
+            .replace("0.0.0.0 news.example.org\n", "");
+        let sites = sites(&["example.com", "news.example.org"]);
+
+        assert_eq!(
+            missing_blocks(&edited, &sites),
+            ["example.com", "news.example.org"]
+        );
+    }
+
+    #[test]
+    fn lines_blocking_several_hosts_are_recognized() {
+        let hosts = "0.0.0.0\texample.com   www.example.com\n  0.0.0.0 example.org\n";
+        let sites = sites(&["example.com", "example.org"]);
+
+        assert!(missing_blocks(hosts, &sites).is_empty());
+    }
+
+    #[test]
+    fn each_domain_is_reported_once() {
+        let sites = sites(&[
+            "video.example.net",
+            "https://www.video.example.net/",
+            "VIDEO.example.net",
+        ]);
+
+        assert_eq!(missing_blocks(HOSTS, &sites), ["video.example.net"]);
+    }
+
+    #[test]
+    fn rules_with_a_path_are_left_to_the_browser() {
+        let sites = sites(&["video.example.net/shorts*", "example.com"]);
+
+        assert!(missing_blocks(HOSTS, &sites).is_empty());
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use base64::Engine;
+
+use crate::xdg;
+
+const FALLBACK_THEME: &str = "hicolor";
+const EXTENSIONS: [&str; 2] = ["png", "svg"];
+
+#[derive(Debug, Clone)]
+enum SizeType {
+    Fixed,
+    Scalable { min: u32, max: u32 },
+    Threshold(u32),
+}
+
+#[derive(Debug, Clone)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    size_type: SizeType,
+}
+
+impl ThemeDirectory {
+    fn matches(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.size_type {
+            SizeType::Fixed => self.size == size,
+            SizeType::Scalable { min, max } => (min..=max).contains(&size),
+            SizeType::Threshold(t) => {
+                self.size.saturating_sub(t) <= size && size <= self.size + t
+            }
+        }
+    }
+
+    fn distance(&self, size: u32, scale: u32) -> u32 {
+        let wanted = size * scale;
+        let (low, high) = match self.size_type {
+            SizeType::Fixed => (self.size, self.size),
+            SizeType::Scalable { min, max } => (min, max),
+            SizeType::Threshold(t) => (self.size.saturating_sub(t), self.size + t),
+        };
+        let (low, high) = (low * self.scale, high * self.scale);
+
+        low.saturating_sub(wanted) + wanted.saturating_sub(high)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IconTheme {
+    /// Every `icons/<theme>` directory that exists, in base directory order.
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+/// Resolves freedesktop icon names (the `Icon=` key of desktop files) to
+/// data URLs the webview can render, caching both parsed themes and lookups.
+pub struct IconResolver {
+    theme_name: String,
+    themes: Mutex<HashMap<String, Option<IconTheme>>>,
+    lookups: Mutex<HashMap<(String, u32), Option<String>>>,
+}
+
+impl IconResolver {
+    pub fn new() -> Self {
+        Self {
+            theme_name: current_theme_name().unwrap_or_else(|| FALLBACK_THEME.to_string()),
+            themes: Mutex::new(HashMap::new()),
+            lookups: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn resolve_data_url(&self, icon: &str, size: u32) -> Option<String> {
+        let key = (icon.to_string(), size);
+        if let Some(cached) = self.lookups.lock().unwrap().get(&key) {
+            return cached.clone();
+        }
+
+        let url = self.resolve_path(icon, size).and_then(|p| to_data_url(&p));
+        self.lookups.lock().unwrap().insert(key, url.clone());
+        url
+    }
+
+    /// Follows the icon theme lookup algorithm: the current theme and its
+    /// parents, then hicolor, then the unthemed pixmaps directories.
+    pub fn resolve_path(&self, icon: &str, size: u32) -> Option<PathBuf> {
+        let icon = icon.trim();
+        if icon.is_empty() {
+            return None;
+        }
+
+        if icon.starts_with('/') {
+            let path = PathBuf::from(icon);
+            return path.is_file().then_some(path);
+        }
+        // A name is joined onto theme directories, so it must not climb out
+        if icon.contains('/') {
+            return None;
+        }
+
+        // Some desktop files wrongly include the extension in Icon=
+        let icon = EXTENSIONS
+            .iter()
+            .chain(["xpm"].iter())
+            .find_map(|ext| icon.strip_suffix(&format!(".{}", ext)))
+            .unwrap_or(icon);
+
+        let mut visited = Vec::new();
+        self.lookup_in_theme(&self.theme_name.clone(), icon, size, &mut visited)
+            .or_else(|| self.lookup_in_theme(FALLBACK_THEME, icon, size, &mut visited))
+            .or_else(|| lookup_fallback(icon))
+    }
+
+    fn lookup_in_theme(
+        &self,
+        theme_name: &str,
+        icon: &str,
+        size: u32,
+        visited: &mut Vec<String>,
+    ) -> Option<PathBuf> {
+        if visited.iter().any(|t| t == theme_name) {
+            return None;
+        }
+        visited.push(theme_name.to_string());
+
+        let theme = self.load_theme(theme_name)?;
+
+        if let Some(path) = lookup_icon(&theme, icon, size, 1) {
+            return Some(path);
+        }
+
+        theme
+            .inherits
+            .iter()
+            .find_map(|parent| self.lookup_in_theme(parent, icon, size, visited))
+    }
+
+    fn load_theme(&self, theme_name: &str) -> Option<IconTheme> {
+        let mut themes = self.themes.lock().unwrap();
+        themes
+            .entry(theme_name.to_string())
+            .or_insert_with(|| parse_theme(theme_name))
+            .clone()
+    }
+}
+
+fn lookup_icon(theme: &IconTheme, icon: &str, size: u32, scale: u32) -> Option<PathBuf> {
+    for dir in theme.directories.iter().filter(|d| d.matches(size, scale)) {
+        if let Some(path) = find_icon_file(&theme.roots, &dir.path, icon) {
+            return Some(path);
+        }
+    }
+
+    let mut best = None;
+    let mut best_distance = u32::MAX;
+    for dir in &theme.directories {
+        let distance = dir.distance(size, scale);
+        if distance < best_distance {
+            if let Some(path) = find_icon_file(&theme.roots, &dir.path, icon) {
+                best = Some(path);
+                best_distance = distance;
+            }
+        }
+    }
+
+    best
+}
+
+fn find_icon_file(roots: &[PathBuf], subdir: &str, icon: &str) -> Option<PathBuf> {
+    roots.iter().find_map(|root| {
+        EXTENSIONS
+            .iter()
+            .map(|ext| root.join(subdir).join(format!("{}.{}", icon, ext)))
+            .find(|path| path.is_file())
+    })
+}
+
+fn lookup_fallback(icon: &str) -> Option<PathBuf> {
+    base_dirs()
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from("/usr/share/pixmaps")))
+        .flat_map(|dir| {
+            EXTENSIONS
+                .iter()
+                .map(move |ext| dir.join(format!("{}.{}", icon, ext)))
+        })
+        .find(|path| path.is_file())
+}
+
+/// `~/.icons`, then `icons` under `$XDG_DATA_HOME` and each `$XDG_DATA_DIRS`.
+fn base_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").unwrap_or_default();
+    std::iter::once(PathBuf::from(format!("{}/.icons", home)))
+        .chain(xdg::data_paths("icons"))
+        .collect()
+}
+
+fn parse_theme(theme_name: &str) -> Option<IconTheme> {
+    let roots: Vec<PathBuf> = base_dirs()
+        .into_iter()
+        .map(|dir| dir.join(theme_name))
+        .filter(|dir| dir.is_dir())
+        .collect();
+
+    let index = roots
+        .iter()
+        .find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+    let groups = read_groups(&index);
+    let main = groups.get("Icon Theme")?;
+
+    let list = |value: Option<&String>| -> Vec<String> {
+        value
+            .map(|v| {
+                v.split(',')
+                    .map(|s| s.trim().to_string())
+                    .filter(|s| !s.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let mut directory_names = list(main.get("Directories"));
+    directory_names.extend(list(main.get("ScaledDirectories")));
+
+    let directories = directory_names
+        .into_iter()
+        .filter_map(|path| {
+            let group = groups.get(&path)?;
+            let number = |key: &str| group.get(key).and_then(|v| v.trim().parse::<u32>().ok());
+            let size = number("Size")?;
+
+            let size_type = match group.get("Type").map(|t| t.trim()) {
+                Some("Fixed") => SizeType::Fixed,
+                Some("Scalable") => SizeType::Scalable {
+                    min: number("MinSize").unwrap_or(size),
+                    max: number("MaxSize").unwrap_or(size),
+                },
+                _ => SizeType::Threshold(number("Threshold").unwrap_or(2)),
+            };
+
+            Some(ThemeDirectory {
+                path,
+                size,
+                scale: number("Scale").unwrap_or(1),
+                size_type,
+            })
+        })
+        .collect();
+
+    Some(IconTheme {
+        roots,
+        directories,
+        inherits: list(main.get("Inherits")),
+    })
+}
+
+fn read_groups(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut groups: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if line.starts_with('[') && line.ends_with(']') {
+            let name = line[1..line.len() - 1].to_string();
+            groups.entry(name.clone()).or_default();
+            current = Some(name);
+            continue;
+        }
+
+        if let (Some(group), Some((key, value))) = (current.as_ref(), line.split_once('=')) {
+            groups
+                .entry(group.clone())
+                .or_default()
+                .entry(key.trim().to_string())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+
+    groups
+}
+
+/// The icon theme configured for the desktop: GNOME's gsettings key, then
+/// the GTK 3 settings file, then KDE's kdeglobals.
+fn current_theme_name() -> Option<String> {
+    let from_gsettings = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "icon-theme"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().trim_matches('\'').to_string())
+        .filter(|name| !name.is_empty());
+
+    if from_gsettings.is_some() {
+        return from_gsettings;
+    }
+
+    let config_home = xdg::config_home();
+
+    let from_file = |file: &str, group: &str, key: &str| {
+        let content = fs::read_to_string(config_home.join(file)).ok()?;
+        read_groups(&content)
+            .get(group)?
+            .get(key)
+            .filter(|name| !name.is_empty())
+            .cloned()
+    };
+
+    from_file("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name")
+        .or_else(|| from_file("kdeglobals", "Icons", "Theme"))
+}
+
+fn to_data_url(path: &Path) -> Option<String> {
+    let mime = match path.extension().and_then(|e| e.to_str()) {
+        Some("png") => "image/png",
+        Some("svg") => "image/svg+xml",
+        _ => return None,
+    };
+
+    let bytes = fs::read(path).ok()?;
+    Some(format!(
+        "data:{};base64,{}",
+        mime,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
@@ -1,44 +1,164 @@
+mod app_index;
 mod block;
+mod blocklist;
+mod blocklist_import;
+pub mod cli;
 mod commands;
+mod connections;
+mod dbus;
 mod app_blocker;
+mod desktop_entry;
+mod dnd;
+mod dns;
+mod hooks;
+mod hosts_guard;
+mod icon_theme;
+pub mod native_messaging;
+mod notifications;
+mod pomodoro;
+mod profiles;
+mod reminders;
+mod schedule;
+mod session;
+mod shortcuts;
+mod status;
+mod store;
+mod tray;
+mod windows;
+mod xdg;
 
 use tauri::Manager;
 use std::sync::Mutex;
 use app_blocker::AppBlocker;
+use app_index::AppIndex;
+use blocklist::BlocklistRegistry;
+use dnd::Dnd;
+use hooks::HookStore;
+use hosts_guard::HostsGuard;
+use native_messaging::BrowserBridge;
+use pomodoro::PomodoroState;
+use profiles::ProfileStore;
+use schedule::Scheduler;
+use session::SessionState;
+use shortcuts::ShortcutStore;
+use windows::WindowStateStore;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(shortcuts::on_shortcut)
+                .build(),
+        )
         .setup(|app| {
+            notifications::set_app(app.handle().clone());
+
             // Initialize app blocker state
             app.manage(Mutex::new(AppBlocker::new()));
-            
-            if let Some(window) = app.get_webview_window("main") {
-                use tauri::PhysicalSize;
-                let _ = window.unmaximize();
-                let _ = window.set_fullscreen(false);
-                let _ = window.set_size(PhysicalSize::new(1600, 1200));
-                let _ = window.center();
+
+            let app_index = AppIndex::new();
+            app_index.seed_usage(commands::blocked_app_history());
+            if let Err(e) = app_index.watch() {
+                eprintln!("App search will not refresh automatically: {}", e);
+            }
+            app.manage(app_index);
+
+            let browser_bridge = BrowserBridge::new();
+            if let Err(e) = browser_bridge.listen() {
+                eprintln!("Browser extensions will not receive rules: {}", e);
+            }
+            app.manage(browser_bridge);
+
+            app.manage(HostsGuard::new());
+
+            app.manage(BlocklistRegistry::load());
+            app.manage(ProfileStore::load());
+            app.manage(SessionState::load());
+            app.manage(PomodoroState::default());
+            app.manage(Scheduler::load());
+            app.manage(ShortcutStore::load());
+            app.manage(HookStore::load());
+            app.manage(Dnd::load());
+            app.manage(WindowStateStore::load());
+            commands::restore_session(app.handle());
+            commands::watch_deadline(app.handle().clone());
+
+            match dbus::serve(app.handle()) {
+                Ok(connection) => {
+                    app.manage(connection);
+                }
+                Err(e) => eprintln!("Desktop integration over D-Bus is unavailable: {}", e),
             }
+
+            if let Err(e) = status::start(app.handle().clone()) {
+                eprintln!("Status bar widgets will not receive updates: {}", e);
+            }
+
+            schedule::start(app.handle().clone());
+            reminders::start(app.handle().clone());
+            hooks::listen(app.handle());
+            shortcuts::register_saved(app.handle());
+
+            if let Err(e) = tray::setup(app.handle()) {
+                eprintln!("Failed to create the tray icon: {}", e);
+            }
+
+            windows::setup(app.handle());
             Ok(())
         })
+        .on_window_event(|window, event| {
+            tray::on_window_event(window, event);
+            windows::on_window_event(window, event);
+        })
         .invoke_handler(tauri::generate_handler![
             commands::setup_persistent_authorization,
             commands::check_authorization_status,
             commands::remove_authorization,
             commands::create_and_store_session,
+            commands::start_session_from_profile,
+            commands::start_pomodoro,
+            commands::get_pomodoro_status,
+            commands::pause_session,
+            commands::resume_session,
             commands::unblock_all_sites,
+            commands::request_unlock_challenge,
+            commands::complete_unlock_challenge,
             commands::get_all_sessions,
-            commands::resize_window_to_widget,
-            commands::resize_window_to_main,
-            commands::resize_window_to_stats,
+            commands::show_window,
+            commands::close_window,
+            commands::list_blocklists,
+            commands::create_blocklist,
+            commands::update_blocklist,
+            commands::delete_blocklist,
+            commands::import_blocklist,
+            commands::list_profiles,
+            commands::create_profile,
+            commands::update_profile,
+            commands::delete_profile,
+            commands::list_schedules,
+            commands::create_schedule,
+            commands::update_schedule,
+            commands::delete_schedule,
+            commands::list_hooks,
+            commands::update_hooks,
+            commands::get_shortcuts,
+            commands::update_shortcuts,
+            commands::get_active_session,
             commands::search_apps,
+            commands::resolve_icon,
             commands::start_app_blocking,
             commands::stop_app_blocking,
             commands::get_block_attempts,
-            commands::show_session_complete_notification
+            commands::get_blocked_visits,
+            commands::install_browser_integration,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                windows::save_geometry(app);
+            }
+        });
 }
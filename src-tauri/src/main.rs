@@ -1,5 +1,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if brisq_lib::native_messaging::is_host_invocation(&args) {
+        std::process::exit(brisq_lib::native_messaging::run_host());
+    }
+
     brisq_lib::run()
 }
@@ -0,0 +1,666 @@
+use std::io::{self, Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+
+use crate::block::SiteRule;
+use crate::hooks::{self, BlockedKind};
+use crate::xdg;
+
+/// Name of the native messaging host in the browser manifests.
+pub const HOST_NAME: &str = "org.brisk.focus";
+pub const FIREFOX_EXTENSION_ID: &str = "focus@brisk.app";
+
+/// Browsers cap messages to the host at 4 GiB and from the host at 1 MiB;
+/// anything near either is a broken client.
+const MAX_MESSAGE_SIZE: u32 = 1024 * 1024;
+/// A browser that stops reading must not hold up the session that is
+/// pushing rules to it.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Messages sent by the browser extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ExtensionMessage {
+    Hello {
+        browser: String,
+        #[serde(default)]
+        version: Option<String>,
+    },
+    GetRules,
+    BlockedVisit {
+        url: String,
+        #[serde(default)]
+        timestamp: Option<i64>,
+    },
+}
+
+/// Messages sent to the browser extension.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HostMessage {
+    Rules {
+        active: bool,
+        rules: Vec<SiteRule>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        ends_at: Option<i64>,
+    },
+    Error {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BlockedVisit {
+    pub url: String,
+    pub domain: String,
+    pub browser: Option<String>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct SessionRules {
+    active: bool,
+    rules: Vec<SiteRule>,
+    ends_at: Option<i64>,
+}
+
+impl SessionRules {
+    fn message(&self) -> HostMessage {
+        HostMessage::Rules {
+            active: self.active,
+            rules: self.rules.clone(),
+            ends_at: self.ends_at,
+        }
+    }
+}
+
+/// Reads one native messaging frame: a native-endian `u32` length followed
+/// by that many bytes of UTF-8 JSON. Returns `None` on a clean EOF.
+pub fn read_message<R: Read, T: for<'de> Deserialize<'de>>(reader: &mut R) -> io::Result<Option<T>> {
+    let mut len = [0u8; 4];
+    match reader.read_exact(&mut len) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e),
+    }
+
+    let len = u32::from_ne_bytes(len);
+    if len > MAX_MESSAGE_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("Message of {} bytes exceeds the limit", len),
+        ));
+    }
+
+    let mut body = vec![0u8; len as usize];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+pub fn write_message<W: Write + ?Sized, T: Serialize>(writer: &mut W, message: &T) -> io::Result<()> {
+    let body = serde_json::to_vec(message).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    writer.write_all(&(body.len() as u32).to_ne_bytes())?;
+    writer.write_all(&body)?;
+    writer.flush()
+}
+
+type ClientWriter = Box<dyn Write + Send>;
+/// Each client's writer is locked on its own, so a slow client only holds
+/// up messages to itself.
+type Client = (u64, Arc<Mutex<ClientWriter>>);
+
+/// The app side of the browser integration. Holds the rules of the current
+/// session, pushes them to every connected extension when they change and
+/// collects the blocked visits extensions report back.
+#[derive(Clone, Default)]
+pub struct BrowserBridge {
+    rules: Arc<Mutex<SessionRules>>,
+    clients: Arc<Mutex<Vec<Client>>>,
+    next_client_id: Arc<Mutex<u64>>,
+    visits: Arc<Mutex<Vec<BlockedVisit>>>,
+}
+
+impl BrowserBridge {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Activates the rules derived from a session's blocked sites.
+    pub fn set_session(&self, blocked_things: &[String], ends_at: Option<i64>) {
+        {
+            let mut rules = self.rules.lock().unwrap();
+            rules.active = true;
+            rules.rules = blocked_things
+                .iter()
+                .filter_map(|s| SiteRule::parse(s))
+                .collect();
+            rules.ends_at = ends_at;
+        }
+        self.visits.lock().unwrap().clear();
+        self.broadcast();
+    }
+
+    pub fn clear_session(&self) {
+        *self.rules.lock().unwrap() = SessionRules::default();
+        self.broadcast();
+    }
+
+    pub fn blocked_visits(&self) -> Vec<BlockedVisit> {
+        self.visits.lock().unwrap().clone()
+    }
+
+    fn current_rules(&self) -> HostMessage {
+        self.rules.lock().unwrap().message()
+    }
+
+    fn broadcast(&self) {
+        let message = self.current_rules();
+        let clients = self.clients.lock().unwrap().clone();
+
+        let failed: Vec<u64> = clients
+            .iter()
+            .filter(|(_, writer)| write_message(writer.lock().unwrap().as_mut(), &message).is_err())
+            .map(|(id, _)| *id)
+            .collect();
+        // Drop clients whose browser went away
+        if !failed.is_empty() {
+            self.clients
+                .lock()
+                .unwrap()
+                .retain(|(id, _)| !failed.contains(id));
+        }
+    }
+
+    /// Speaks the extension protocol on one connection until the client
+    /// disconnects. Works over any byte stream, so tests drive it with
+    /// in-memory pipes instead of a browser.
+    pub fn serve_client<R: Read>(&self, mut input: R, output: ClientWriter) -> io::Result<()> {
+        let client_id = {
+            let mut next = self.next_client_id.lock().unwrap();
+            *next += 1;
+            *next
+        };
+        self.clients
+            .lock()
+            .unwrap()
+            .push((client_id, Arc::new(Mutex::new(output))));
+
+        let mut browser = None;
+        let result = loop {
+            let message = match read_message::<_, ExtensionMessage>(&mut input) {
+                Ok(Some(message)) => message,
+                Ok(None) => break Ok(()),
+                // A malformed body was fully consumed, so the stream is
+                // still in sync and the client can carry on
+                Err(e) if e.get_ref().is_some_and(|inner| inner.is::<serde_json::Error>()) => {
+                    self.send_to(client_id, &HostMessage::Error {
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+                Err(e) => break Err(e),
+            };
+
+            match message {
+                ExtensionMessage::Hello { browser: name, .. } => {
+                    browser = Some(name);
+                    self.send_to(client_id, &self.current_rules());
+                }
+                ExtensionMessage::GetRules => {
+                    self.send_to(client_id, &self.current_rules());
+                }
+                ExtensionMessage::BlockedVisit { url, timestamp } => {
+                    self.record_visit(url, browser.clone(), timestamp);
+                }
+            }
+        };
+
+        self.clients.lock().unwrap().retain(|(id, _)| *id != client_id);
+        result
+    }
+
+    fn send_to(&self, client_id: u64, message: &HostMessage) {
+        let writer = self
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(id, _)| *id == client_id)
+            .map(|(_, writer)| Arc::clone(writer));
+
+        if let Some(writer) = writer {
+            if let Err(e) = write_message(writer.lock().unwrap().as_mut(), message) {
+                eprintln!("Failed to write to browser extension: {}", e);
+            }
+        }
+    }
+
+    fn record_visit(&self, url: String, browser: Option<String>, timestamp: Option<i64>) {
+        let (host, path) = split_url(&url);
+        // Attribute the visit to the rule that blocked it, so a visit to
+        // m.youtube.com/shorts counts against youtube.com
+        let domain = self
+            .rules
+            .lock()
+            .unwrap()
+            .rules
+            .iter()
+            .find(|rule| rule.matches(host, path))
+            .map(|rule| rule.domain.clone())
+            .unwrap_or_else(|| host.to_lowercase());
+
+        println!("Browser blocked a visit to {}", url);
+        hooks::spawn_block_attempt(BlockedKind::Site, &domain);
+        self.visits.lock().unwrap().push(BlockedVisit {
+            url,
+            domain,
+            browser,
+            timestamp: timestamp.unwrap_or_else(|| Utc::now().timestamp()),
+        });
+    }
+
+    /// Accepts connections from native messaging host processes on the
+    /// bridge socket, one thread per connected browser.
+    pub fn listen(&self) -> Result<(), String> {
+        let path = socket_path();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Failed to create socket directory: {}", e))?;
+        }
+        // A socket left behind by a crashed instance would make bind fail
+        let _ = std::fs::remove_file(&path);
+
+        let listener = UnixListener::bind(&path)
+            .map_err(|e| format!("Failed to bind browser socket {:?}: {}", path, e))?;
+
+        let bridge = self.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let bridge = bridge.clone();
+                thread::spawn(move || {
+                    let Ok(writer) = stream.try_clone() else {
+                        return;
+                    };
+                    let _ = writer.set_write_timeout(Some(WRITE_TIMEOUT));
+                    if let Err(e) = bridge.serve_client(stream, Box::new(writer)) {
+                        eprintln!("Browser extension connection failed: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+/// Splits a URL into its host and path, dropping scheme, credentials, port,
+/// query and fragment.
+fn split_url(url: &str) -> (&str, &str) {
+    let rest = url.split_once("://").map(|(_, rest)| rest).unwrap_or(url);
+    let end = rest.find(['?', '#']).unwrap_or(rest.len());
+    let rest = &rest[..end];
+
+    let (authority, path) = match rest.find('/') {
+        Some(index) => rest.split_at(index),
+        None => (rest, "/"),
+    };
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host);
+
+    (host, path)
+}
+
+pub fn socket_path() -> PathBuf {
+    xdg::runtime_dir().join("brisk").join("browser.sock")
+}
+
+/// Browsers start the host with the caller's origin: Chromium passes
+/// `chrome-extension://<id>/`, Firefox the manifest path and the add-on ID.
+pub fn is_host_invocation(args: &[String]) -> bool {
+    args.get(1).is_some_and(|arg| {
+        arg.starts_with("chrome-extension://")
+            || (arg.ends_with(&format!("{}.json", HOST_NAME)) && args.len() >= 3)
+    })
+}
+
+/// Entry point of the native messaging host process the browser spawns.
+/// Relays frames between the extension on stdio and the running app's
+/// bridge socket, which speaks the same framing.
+pub fn run_host() -> i32 {
+    let mut stdout = io::stdout();
+
+    let stream = match UnixStream::connect(socket_path()) {
+        Ok(stream) => stream,
+        Err(_) => {
+            // Brisk is not running, so there is no session to enforce
+            let _ = write_message(
+                &mut stdout,
+                &HostMessage::Rules {
+                    active: false,
+                    rules: Vec::new(),
+                    ends_at: None,
+                },
+            );
+            return 0;
+        }
+    };
+
+    let Ok(mut from_app) = stream.try_clone() else {
+        return 1;
+    };
+    let mut to_app = stream;
+
+    thread::spawn(move || {
+        let _ = io::copy(&mut io::stdin(), &mut to_app);
+        let _ = to_app.shutdown(std::net::Shutdown::Write);
+    });
+
+    match io::copy(&mut from_app, &mut stdout) {
+        Ok(_) => 0,
+        Err(_) => 1,
+    }
+}
+
+/// Registers the host with Firefox and Chromium-based browsers for the
+/// current user. Returns the manifest paths that were written.
+pub fn install_manifests(chrome_extension_ids: &[String]) -> Result<Vec<PathBuf>, String> {
+    let host_path = std::env::current_exe()
+        .map_err(|e| format!("Failed to locate the Brisk executable: {}", e))?;
+    let host_path = host_path
+        .to_str()
+        .ok_or("Brisk executable path is not valid UTF-8")?;
+
+    let home = std::env::var("HOME").map_err(|_| "Failed to get home directory".to_string())?;
+    let config = xdg::config_home();
+
+    let firefox_manifest = serde_json::json!({
+        "name": HOST_NAME,
+        "description": "Brisk focus session rules",
+        "path": host_path,
+        "type": "stdio",
+        "allowed_extensions": [FIREFOX_EXTENSION_ID],
+    });
+    let chrome_manifest = serde_json::json!({
+        "name": HOST_NAME,
+        "description": "Brisk focus session rules",
+        "path": host_path,
+        "type": "stdio",
+        "allowed_origins": chrome_extension_ids
+            .iter()
+            .map(|id| format!("chrome-extension://{}/", id))
+            .collect::<Vec<_>>(),
+    });
+
+    let mut targets = vec![(
+        PathBuf::from(format!("{}/.mozilla/native-messaging-hosts", home)),
+        &firefox_manifest,
+    )];
+    if !chrome_extension_ids.is_empty() {
+        for browser in [
+            "google-chrome",
+            "chromium",
+            "BraveSoftware/Brave-Browser",
+            "microsoft-edge",
+            "vivaldi",
+        ] {
+            targets.push((config.join(browser).join("NativeMessagingHosts"), &chrome_manifest));
+        }
+    }
+
+    let content = |manifest: &serde_json::Value| {
+        serde_json::to_string_pretty(manifest).map_err(|e| format!("Failed to serialize manifest: {}", e))
+    };
+
+    let mut written = Vec::new();
+    for (dir, manifest) in targets {
+        // Only register with browsers that are installed for this user
+        if !dir.parent().is_some_and(|p| p.is_dir()) {
+            continue;
+        }
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create {:?}: {}", dir, e))?;
+        let path = dir.join(format!("{}.json", HOST_NAME));
+        std::fs::write(&path, content(manifest)?)
+            .map_err(|e| format!("Failed to write {:?}: {}", path, e))?;
+        written.push(path);
+    }
+
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufReader, Cursor};
+
+    /// Collects what the bridge writes to a client.
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Output {
+        fn messages(&self) -> Vec<HostMessage> {
+            let bytes = self.0.lock().unwrap().clone();
+            let mut reader = Cursor::new(bytes);
+            let mut messages = Vec::new();
+            while let Some(message) = read_message(&mut reader).unwrap() {
+                messages.push(message);
+            }
+            messages
+        }
+    }
+
+    /// A browser whose pipe has gone away.
+    struct Closed;
+
+    impl Write for Closed {
+        fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+            Err(io::ErrorKind::BrokenPipe.into())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Frames `messages` the way the extension sends them on stdio.
+    fn input(messages: &[serde_json::Value]) -> Cursor<Vec<u8>> {
+        let mut bytes = Vec::new();
+        for message in messages {
+            write_message(&mut bytes, message).unwrap();
+        }
+        Cursor::new(bytes)
+    }
+
+    fn rules(message: &HostMessage) -> (bool, Vec<String>) {
+        match message {
+            HostMessage::Rules { active, rules, .. } => (
+                *active,
+                rules.iter().map(|rule| rule.domain.clone()).collect(),
+            ),
+            other => panic!("Expected rules, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn frames_round_trip() {
+        let mut bytes = Vec::new();
+        write_message(&mut bytes, &ExtensionMessage::GetRules).unwrap();
+        assert_eq!(&bytes[..4], &(bytes.len() as u32 - 4).to_ne_bytes());
+
+        let mut reader = Cursor::new(bytes);
+        let message: Option<ExtensionMessage> = read_message(&mut reader).unwrap();
+        assert_eq!(message, Some(ExtensionMessage::GetRules));
+        assert_eq!(
+            read_message::<_, ExtensionMessage>(&mut reader).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn oversized_frames_are_refused() {
+        let mut reader = Cursor::new((MAX_MESSAGE_SIZE + 1).to_ne_bytes().to_vec());
+        assert!(read_message::<_, ExtensionMessage>(&mut reader).is_err());
+    }
+
+    #[test]
+    fn hello_is_answered_with_the_session_rules() {
+        let bridge = BrowserBridge::new();
+        bridge.set_session(&["youtube.com/shorts".to_string()], Some(100));
+        let output = Output::default();
+
+        let client = input(&[serde_json::json!({"type": "hello", "browser": "firefox"})]);
+        bridge
+            .serve_client(client, Box::new(output.clone()))
+            .unwrap();
+
+        let messages = output.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(rules(&messages[0]), (true, vec!["youtube.com".to_string()]));
+    }
+
+    #[test]
+    fn rules_are_inactive_without_a_session() {
+        let bridge = BrowserBridge::new();
+        let output = Output::default();
+
+        bridge
+            .serve_client(
+                input(&[serde_json::json!({"type": "get_rules"})]),
+                Box::new(output.clone()),
+            )
+            .unwrap();
+
+        assert_eq!(rules(&output.messages()[0]), (false, Vec::new()));
+    }
+
+    #[test]
+    fn malformed_messages_get_an_error_and_the_client_carries_on() {
+        let bridge = BrowserBridge::new();
+        let output = Output::default();
+
+        let client = input(&[
+            serde_json::json!({"type": "launch_missiles"}),
+            serde_json::json!({"type": "get_rules"}),
+        ]);
+        bridge
+            .serve_client(client, Box::new(output.clone()))
+            .unwrap();
+
+        let messages = output.messages();
+        assert!(matches!(messages[0], HostMessage::Error { .. }));
+        assert!(!rules(&messages[1]).0);
+    }
+
+    #[test]
+    fn blocked_visits_count_against_the_rule_that_blocked_them() {
+        let bridge = BrowserBridge::new();
+        bridge.set_session(&["youtube.com/shorts".to_string()], None);
+
+        let client = input(&[
+            serde_json::json!({"type": "hello", "browser": "chromium"}),
+            serde_json::json!({
+                "type": "blocked_visit",
+                "url": "https://m.youtube.com:443/shorts/abc?t=1",
+                "timestamp": 42,
+            }),
+        ]);
+        bridge.serve_client(client, Box::new(io::sink())).unwrap();
+
+        let visits = bridge.blocked_visits();
+        assert_eq!(visits.len(), 1);
+        assert_eq!(visits[0].domain, "youtube.com");
+        assert_eq!(visits[0].browser.as_deref(), Some("chromium"));
+        assert_eq!(visits[0].timestamp, 42);
+    }
+
+    #[test]
+    fn session_changes_are_pushed_to_connected_clients() {
+        let bridge = BrowserBridge::new();
+        let (extension, host) = UnixStream::pair().unwrap();
+
+        let serving = bridge.clone();
+        let server = thread::spawn(move || {
+            let writer = host.try_clone().unwrap();
+            serving.serve_client(host, Box::new(writer))
+        });
+
+        let mut to_host = extension.try_clone().unwrap();
+        let mut from_host = BufReader::new(extension);
+        write_message(
+            &mut to_host,
+            &serde_json::json!({"type": "hello", "browser": "firefox"}),
+        )
+        .unwrap();
+        let hello: HostMessage = read_message(&mut from_host).unwrap().unwrap();
+        assert!(!rules(&hello).0);
+
+        bridge.set_session(&["reddit.com".to_string()], Some(100));
+        let pushed: HostMessage = read_message(&mut from_host).unwrap().unwrap();
+        assert_eq!(rules(&pushed), (true, vec!["reddit.com".to_string()]));
+
+        bridge.clear_session();
+        let cleared: HostMessage = read_message(&mut from_host).unwrap().unwrap();
+        assert!(!rules(&cleared).0);
+
+        to_host.shutdown(std::net::Shutdown::Write).unwrap();
+        server.join().unwrap().unwrap();
+        assert!(bridge.clients.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn clients_that_went_away_are_dropped_on_broadcast() {
+        let bridge = BrowserBridge::new();
+        bridge
+            .clients
+            .lock()
+            .unwrap()
+            .push((1, Arc::new(Mutex::new(Box::new(Closed)))));
+        let output = Output::default();
+        bridge
+            .clients
+            .lock()
+            .unwrap()
+            .push((2, Arc::new(Mutex::new(Box::new(output.clone())))));
+
+        bridge.set_session(&["reddit.com".to_string()], None);
+
+        let clients: Vec<u64> = bridge
+            .clients
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, _)| *id)
+            .collect();
+        assert_eq!(clients, [2]);
+        assert_eq!(output.messages().len(), 1);
+    }
+
+    #[test]
+    fn urls_split_into_host_and_path() {
+        assert_eq!(
+            split_url("https://user:pw@Example.com:8080/a/b?q=1#frag"),
+            ("Example.com", "/a/b")
+        );
+        assert_eq!(split_url("example.com"), ("example.com", "/"));
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
+use tauri::{AppHandle, Manager};
+use zbus::blocking::Connection;
+use zbus::zvariant::Value;
+
+use crate::commands;
+use crate::pomodoro::PomodoroState;
+use crate::session::SessionState;
+
+const APP_NAME: &str = "Brisk";
+const DESKTOP_ENTRY: &str = "brisk";
+/// At most this many notifications of one kind are shown per window, so a
+/// blocked app that keeps relaunching cannot flood the desktop.
+pub const RATE_LIMIT: usize = 5;
+pub const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(30);
+/// In seconds.
+const SNOOZE_DURATION: u64 = 5 * 60;
+
+#[zbus::proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+
+    /// A KDE Plasma extension that holds back notifications until the
+    /// cookie is released or the caller disconnects.
+    fn inhibit(
+        &self,
+        desktop_entry: &str,
+        reason: &str,
+        hints: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<u32>;
+
+    fn un_inhibit(&self, cookie: u32) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn action_invoked(&self, id: u32, action_key: String) -> zbus::Result<()>;
+}
+
+/// Each kind of notification has a single bubble that newer ones replace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Kind {
+    Blocked,
+    SessionComplete,
+    Pomodoro,
+    Reminder,
+}
+
+impl Kind {
+    fn urgency(self) -> u8 {
+        match self {
+            Kind::Blocked => 2,
+            // Desktops in Do-Not-Disturb, GNOME among them, still show
+            // critical notifications
+            _ if DO_NOT_DISTURB.load(Ordering::SeqCst) => 2,
+            Kind::SessionComplete | Kind::Pomodoro | Kind::Reminder => 1,
+        }
+    }
+
+    /// In milliseconds.
+    fn timeout(self) -> i32 {
+        match self {
+            Kind::Blocked => 3000,
+            Kind::SessionComplete | Kind::Pomodoro | Kind::Reminder => 5000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    EndSession,
+    /// Pauses the session, and with it blocking, for five minutes.
+    Snooze,
+    /// Cuts the current Pomodoro work interval short.
+    StartBreak,
+}
+
+impl Action {
+    const ALL: [Action; 3] = [Action::EndSession, Action::Snooze, Action::StartBreak];
+
+    fn key(self) -> &'static str {
+        match self {
+            Action::EndSession => "end-session",
+            Action::Snooze => "snooze",
+            Action::StartBreak => "start-break",
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Action::EndSession => "End session",
+            Action::Snooze => "Pause 5 min",
+            Action::StartBreak => "Start break",
+        }
+    }
+
+    fn from_key(key: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.key() == key)
+    }
+}
+
+pub struct Notification<'a> {
+    pub kind: Kind,
+    pub icon: &'a str,
+    pub summary: &'a str,
+    pub body: &'a str,
+    pub actions: &'a [Action],
+}
+
+/// A length of time as notification text, rounded to whole minutes once
+/// it is half a minute or more, e.g. "5 minutes".
+pub fn format_duration(seconds: i64) -> String {
+    let minutes = (seconds + 30) / 60;
+    match minutes {
+        0 => format!("{} seconds", seconds.max(0)),
+        1 => "1 minute".to_string(),
+        _ => format!("{} minutes", minutes),
+    }
+}
+
+/// Allows at most `limit` events per key within a sliding `window`.
+pub struct RateLimiter<K> {
+    limit: usize,
+    window: Duration,
+    sent: Mutex<HashMap<K, VecDeque<Instant>>>,
+}
+
+impl<K: Eq + Hash> RateLimiter<K> {
+    pub fn new(limit: usize, window: Duration) -> Self {
+        Self {
+            limit,
+            window,
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Counts an event for `key`, or returns false without counting it when
+    /// the limit is reached.
+    pub fn allow(&self, key: K) -> bool {
+        self.allow_at(key, Instant::now())
+    }
+
+    fn allow_at(&self, key: K, now: Instant) -> bool {
+        let mut sent = self.sent.lock().unwrap();
+        let times = sent.entry(key).or_default();
+        while times
+            .front()
+            .is_some_and(|t| now.duration_since(*t) > self.window)
+        {
+            times.pop_front();
+        }
+
+        if times.len() >= self.limit {
+            return false;
+        }
+        times.push_back(now);
+        true
+    }
+}
+
+struct Notifier {
+    proxy: NotificationsProxyBlocking<'static>,
+    /// Bubble ids by kind, reused as replace-ids.
+    bubbles: Mutex<HashMap<Kind, u32>>,
+    sent: RateLimiter<Kind>,
+}
+
+static NOTIFIER: OnceLock<Option<Notifier>> = OnceLock::new();
+static APP: OnceLock<AppHandle> = OnceLock::new();
+static DO_NOT_DISTURB: AtomicBool = AtomicBool::new(false);
+
+/// Lets notification buttons act on the app's session. Without it, as in
+/// the CLI, no buttons are offered.
+pub fn set_app(app: AppHandle) {
+    let _ = APP.set(app);
+}
+
+fn notifier() -> Option<&'static Notifier> {
+    NOTIFIER
+        .get_or_init(|| match Notifier::connect() {
+            Ok(notifier) => Some(notifier),
+            Err(e) => {
+                eprintln!("Desktop notifications are unavailable: {}", e);
+                None
+            }
+        })
+        .as_ref()
+}
+
+/// Sends a notification over `org.freedesktop.Notifications`, replacing
+/// the previous one of the same kind. Failures are logged, never returned,
+/// as a missing notification daemon must not break blocking.
+pub fn notify(notification: Notification) {
+    let Some(notifier) = notifier() else {
+        return;
+    };
+    if let Err(e) = notifier.send(&notification) {
+        eprintln!("Failed to show notification: {}", e);
+    }
+}
+
+/// Called while Brisk has Do-Not-Disturb on, so its own notifications are
+/// raised to get through it.
+pub fn set_do_not_disturb(on: bool) {
+    DO_NOT_DISTURB.store(on, Ordering::SeqCst);
+}
+
+/// Holds back other apps' notifications on servers that support it, as
+/// KDE Plasma's does, until [`uninhibit`] is called or Brisk exits.
+pub fn inhibit(reason: &str) -> Result<u32, String> {
+    let notifier = notifier().ok_or("Desktop notifications are unavailable")?;
+    notifier
+        .proxy
+        .inhibit(DESKTOP_ENTRY, reason, HashMap::new())
+        .map_err(|e| format!("Failed to inhibit notifications: {}", e))
+}
+
+pub fn uninhibit(cookie: u32) -> Result<(), String> {
+    let notifier = notifier().ok_or("Desktop notifications are unavailable")?;
+    notifier
+        .proxy
+        .un_inhibit(cookie)
+        .map_err(|e| format!("Failed to release notifications: {}", e))
+}
+
+impl Notifier {
+    fn connect() -> zbus::Result<Self> {
+        let connection = Connection::session()?;
+        let proxy = NotificationsProxyBlocking::new(&connection)?;
+
+        let signals = proxy.receive_action_invoked()?;
+        thread::spawn(move || {
+            for signal in signals {
+                let Ok(args) = signal.args() else {
+                    continue;
+                };
+                let Some(notifier) = notifier() else {
+                    continue;
+                };
+                // The signal is broadcast for every app's notifications
+                let ours = notifier
+                    .bubbles
+                    .lock()
+                    .unwrap()
+                    .values()
+                    .any(|id| *id == args.id);
+                if let (true, Some(action)) = (ours, Action::from_key(&args.action_key)) {
+                    notifier.invoke(action);
+                }
+            }
+        });
+
+        Ok(Self {
+            proxy,
+            bubbles: Mutex::new(HashMap::new()),
+            sent: RateLimiter::new(RATE_LIMIT, RATE_LIMIT_WINDOW),
+        })
+    }
+
+    fn send(&self, notification: &Notification) -> Result<(), String> {
+        if !self.sent.allow(notification.kind) {
+            return Ok(());
+        }
+
+        let actions: Vec<&str> = notification
+            .actions
+            .iter()
+            .filter(|action| is_available(**action))
+            .flat_map(|action| [action.key(), action.label()])
+            .collect();
+        let mut hints = HashMap::new();
+        hints.insert("urgency", Value::from(notification.kind.urgency()));
+
+        let replaces_id = self
+            .bubbles
+            .lock()
+            .unwrap()
+            .get(&notification.kind)
+            .copied()
+            .unwrap_or(0);
+        let id = self
+            .proxy
+            .notify(
+                APP_NAME,
+                replaces_id,
+                notification.icon,
+                notification.summary,
+                notification.body,
+                &actions,
+                hints,
+                // Bubbles with buttons stay until dismissed, so there is time
+                // to press one
+                if actions.is_empty() {
+                    notification.kind.timeout()
+                } else {
+                    0
+                },
+            )
+            .map_err(|e| e.to_string())?;
+
+        self.bubbles.lock().unwrap().insert(notification.kind, id);
+        Ok(())
+    }
+
+    fn invoke(&self, action: Action) {
+        let result = match (action, APP.get()) {
+            (Action::Snooze, Some(app)) => {
+                commands::pause_session(Some(SNOOZE_DURATION), app.clone()).map(|_| ())
+            }
+            (Action::EndSession, Some(app)) => {
+                let now = Utc::now().timestamp();
+                app.state::<SessionState>()
+                    .ensure_unlocked(now)
+                    .and_then(|_| commands::end_session(app))
+                    .map(|_| ())
+            }
+            (Action::StartBreak, Some(app)) => app.state::<PomodoroState>().start_break(),
+            (_, None) => Ok(()),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Notification action {} failed: {}", action.key(), e);
+        }
+    }
+}
+
+/// Whether a button for `action` would do anything right now.
+fn is_available(action: Action) -> bool {
+    let Some(app) = APP.get() else {
+        return false;
+    };
+
+    match action {
+        Action::Snooze => {
+            app.state::<PomodoroState>().current().is_none()
+                && app.state::<SessionState>().current().is_some_and(|s| {
+                    s.pause.is_none()
+                        && s.remaining_pause_budget() > 0
+                        && !s.is_locked(Utc::now().timestamp())
+                })
+        }
+        Action::EndSession => app
+            .state::<SessionState>()
+            .current()
+            .is_some_and(|s| !s.is_locked(Utc::now().timestamp())),
+        Action::StartBreak => app
+            .state::<PomodoroState>()
+            .current()
+            .is_some_and(|p| p.can_start_break()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations_round_to_whole_minutes() {
+        assert_eq!(format_duration(0), "0 seconds");
+        assert_eq!(format_duration(29), "29 seconds");
+        assert_eq!(format_duration(30), "1 minute");
+        assert_eq!(format_duration(89), "1 minute");
+        assert_eq!(format_duration(90), "2 minutes");
+        assert_eq!(format_duration(5 * 60), "5 minutes");
+        assert_eq!(format_duration(-10), "0 seconds");
+    }
+
+    #[test]
+    fn rate_limiter_allows_up_to_the_limit_per_key() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(limiter.allow_at("a", start));
+        assert!(limiter.allow_at("a", start + Duration::from_secs(1)));
+        assert!(!limiter.allow_at("a", start + Duration::from_secs(2)));
+        // Keys are counted separately
+        assert!(limiter.allow_at("b", start + Duration::from_secs(2)));
+    }
+
+    #[test]
+    fn rate_limiter_window_slides() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let start = Instant::now();
+        let at = |seconds| start + Duration::from_secs(seconds);
+
+        assert!(limiter.allow_at("a", at(0)));
+        assert!(limiter.allow_at("a", at(30)));
+        assert!(!limiter.allow_at("a", at(60)));
+        // The first event has left the window, the second has not
+        assert!(limiter.allow_at("a", at(61)));
+        assert!(!limiter.allow_at("a", at(90)));
+        assert!(limiter.allow_at("a", at(91)));
+    }
+
+    #[test]
+    fn refused_events_do_not_count() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert!(limiter.allow_at("a", start));
+        for seconds in [10, 20, 30, 40, 50, 60] {
+            assert!(!limiter.allow_at("a", start + Duration::from_secs(seconds)));
+        }
+        assert!(limiter.allow_at("a", start + Duration::from_secs(61)));
+    }
+}
@@ -0,0 +1,527 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::commands::{self, SessionRequest};
+use crate::notifications::{self, format_duration, Kind, Notification};
+use crate::session::{ActiveSession, SessionState};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+impl Phase {
+    pub fn is_break(self) -> bool {
+        self != Phase::Work
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            Phase::Work => "Focus",
+            Phase::ShortBreak => "Short break",
+            Phase::LongBreak => "Long break",
+        }
+    }
+}
+
+/// Lengths are in seconds.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PomodoroConfig {
+    pub work_intervals: u32,
+    pub work_duration: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    /// A long break replaces the short one after this many work intervals.
+    #[serde(default = "default_long_break_every")]
+    pub long_break_every: u32,
+    /// Keep sites and apps blocked during breaks instead of lifting blocking.
+    #[serde(default)]
+    pub block_during_breaks: bool,
+}
+
+fn default_long_break_every() -> u32 {
+    4
+}
+
+impl PomodoroConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.work_intervals == 0 {
+            return Err("A Pomodoro cycle needs at least one work interval".to_string());
+        }
+        if self.work_duration == 0 {
+            return Err("Work intervals must be longer than zero".to_string());
+        }
+        if self.long_break_every == 0 {
+            return Err("Long break frequency must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+
+    /// Work intervals with a break between each pair; there is no break
+    /// after the last one.
+    pub fn phases(&self) -> Vec<Phase> {
+        let mut phases = Vec::new();
+
+        for n in 1..=self.work_intervals {
+            phases.push(Phase::Work);
+            if n < self.work_intervals {
+                phases.push(if n % self.long_break_every == 0 {
+                    Phase::LongBreak
+                } else {
+                    Phase::ShortBreak
+                });
+            }
+        }
+
+        phases
+    }
+
+    pub fn phase_duration(&self, phase: Phase) -> u64 {
+        match phase {
+            Phase::Work => self.work_duration,
+            Phase::ShortBreak => self.short_break,
+            Phase::LongBreak => self.long_break,
+        }
+    }
+
+    pub fn total_duration(&self) -> u64 {
+        self.phases()
+            .into_iter()
+            .map(|phase| self.phase_duration(phase))
+            .sum()
+    }
+
+    /// The phases from index `first` on, the first of them starting at
+    /// `started_at` and each of the others when the one before it ends.
+    pub fn plan(&self, first: usize, started_at: i64) -> Vec<PlannedPhase> {
+        let mut starts_at = started_at;
+        self.phases()
+            .into_iter()
+            .enumerate()
+            .skip(first)
+            .map(|(index, phase)| {
+                let ends_at = starts_at + self.phase_duration(phase) as i64;
+                let planned = PlannedPhase {
+                    index,
+                    phase,
+                    starts_at,
+                    ends_at,
+                };
+                starts_at = ends_at;
+                planned
+            })
+            .collect()
+    }
+}
+
+/// A phase of a cycle with the times it runs if nothing cuts it short.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlannedPhase {
+    pub index: usize,
+    pub phase: Phase,
+    pub starts_at: i64,
+    pub ends_at: i64,
+}
+
+/// A finished part of a Pomodoro cycle, recorded in the parent session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interval {
+    pub phase: Phase,
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PomodoroStatus {
+    pub phase: Phase,
+    /// Position of the current phase within `total_phases`, from zero.
+    pub index: usize,
+    pub total_phases: usize,
+    pub phase_started_at: i64,
+    pub phase_ends_at: i64,
+    pub config: PomodoroConfig,
+}
+
+impl PomodoroStatus {
+    /// Whether the current phase is work with a break after it.
+    pub fn can_start_break(&self) -> bool {
+        self.phase == Phase::Work && self.index + 1 < self.total_phases
+    }
+}
+
+#[derive(Default)]
+pub struct PomodoroState {
+    current: Mutex<Option<PomodoroStatus>>,
+    break_requested: AtomicBool,
+}
+
+impl PomodoroState {
+    pub fn current(&self) -> Option<PomodoroStatus> {
+        self.current.lock().unwrap().clone()
+    }
+
+    fn set(&self, status: Option<PomodoroStatus>) {
+        self.break_requested.store(false, Ordering::SeqCst);
+        *self.current.lock().unwrap() = status;
+    }
+
+    /// Ends the current work interval early, moving on to the break.
+    pub fn start_break(&self) -> Result<(), String> {
+        if !self.current().is_some_and(|p| p.can_start_break()) {
+            return Err("No work interval to cut short".to_string());
+        }
+        self.break_requested.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/// Starts the parent session and runs its intervals in the background.
+pub fn start(app: AppHandle, request: SessionRequest) -> Result<String, String> {
+    let config = request
+        .pomodoro
+        .clone()
+        .ok_or("Session has no Pomodoro configuration")?;
+    config.validate()?;
+
+    if app.state::<SessionState>().current().is_some() {
+        return Err("A session is already running".to_string());
+    }
+
+    let blocked_things = request.blocked_things.clone();
+    let blocked_apps = request.blocked_apps.clone();
+    let message = commands::start_session(&app, request)?;
+
+    let started_at = app
+        .state::<SessionState>()
+        .current()
+        .map(|s| s.started_at)
+        .unwrap_or_default();
+
+    spawn(
+        app,
+        config,
+        blocked_things,
+        blocked_apps,
+        started_at,
+        0,
+        started_at,
+    );
+
+    Ok(message)
+}
+
+/// Continues the cycle of a session that was running when Brisk last
+/// exited, from the phase after the intervals recorded for it. Returns
+/// false for a session that is not a Pomodoro cycle.
+pub fn restore(app: &AppHandle, session: &ActiveSession) -> bool {
+    let Some((config, intervals)) = commands::recorded_pomodoro(session) else {
+        return false;
+    };
+
+    let recorded_until = intervals
+        .last()
+        .map_or(session.started_at, |interval| interval.ended_at);
+
+    // Phases that ran out while Brisk was not running are recorded as
+    // planned, so the rest of the cycle keeps to the session's end instead
+    // of starting over from now
+    let now = Utc::now().timestamp();
+    let plan = config.plan(intervals.len(), recorded_until);
+    let elapsed = plan.iter().take_while(|p| p.ends_at <= now).count();
+    for planned in &plan[..elapsed] {
+        let interval = Interval {
+            phase: planned.phase,
+            started_at: planned.starts_at,
+            ended_at: planned.ends_at,
+        };
+        if let Err(e) = commands::record_interval(app, interval) {
+            eprintln!("Failed to record Pomodoro interval: {}", e);
+        }
+    }
+    let (first, phase_started_at) = match plan.get(elapsed) {
+        Some(current) => (current.index, current.starts_at),
+        // Nothing is left to run, so the cycle just ends
+        None => (config.phases().len(), now),
+    };
+
+    // Blocking was lifted for a break that is still going on, and there is
+    // nothing to block for a cycle that is over
+    let blocked = config
+        .phases()
+        .get(first)
+        .is_some_and(|phase| !phase.is_break() || config.block_during_breaks);
+    if blocked {
+        if let Err(e) = commands::apply_blocking(
+            app,
+            &session.blocked_things,
+            &session.blocked_apps,
+            session.ends_at,
+        ) {
+            eprintln!("Failed to restore blocking for {}: {}", session.goal, e);
+        }
+    }
+
+    spawn(
+        app.clone(),
+        config,
+        session.blocked_things.clone(),
+        session.blocked_apps.clone(),
+        session.started_at,
+        first,
+        phase_started_at,
+    );
+    true
+}
+
+/// Runs the phases from `first` on in the background, the first of them
+/// having started at `phase_started_at`.
+fn spawn(
+    app: AppHandle,
+    config: PomodoroConfig,
+    blocked_things: Vec<String>,
+    blocked_apps: Vec<String>,
+    session_started_at: i64,
+    first: usize,
+    phase_started_at: i64,
+) {
+    thread::spawn(move || {
+        run(
+            &app,
+            &config,
+            &blocked_things,
+            &blocked_apps,
+            session_started_at,
+            first,
+            phase_started_at,
+        );
+        app.state::<PomodoroState>().set(None);
+    });
+}
+
+fn run(
+    app: &AppHandle,
+    config: &PomodoroConfig,
+    blocked_things: &[String],
+    blocked_apps: &[String],
+    session_started_at: i64,
+    first: usize,
+    mut phase_started_at: i64,
+) {
+    let phases = config.phases();
+
+    for (index, &phase) in phases.iter().enumerate().skip(first) {
+        let status = PomodoroStatus {
+            phase,
+            index,
+            total_phases: phases.len(),
+            phase_started_at,
+            phase_ends_at: phase_started_at + config.phase_duration(phase) as i64,
+            config: config.clone(),
+        };
+        app.state::<PomodoroState>().set(Some(status.clone()));
+        let _ = app.emit("pomodoro-phase", &status);
+
+        // Phases end on schedule rather than whenever this loop wakes up,
+        // so the cycle finishes at the session's end
+        let mut ended_at = status.phase_ends_at;
+        loop {
+            // The session was ended from elsewhere, e.g. the stop button
+            let current = app.state::<SessionState>().current();
+            if current.map(|s| s.started_at) != Some(session_started_at) {
+                return;
+            }
+            if Utc::now().timestamp() >= status.phase_ends_at {
+                break;
+            }
+            if app
+                .state::<PomodoroState>()
+                .break_requested
+                .swap(false, Ordering::SeqCst)
+            {
+                // The rest of the cycle moves up by the time cut
+                ended_at = Utc::now().timestamp();
+                if let Err(e) = app
+                    .state::<SessionState>()
+                    .move_end(ended_at - status.phase_ends_at)
+                {
+                    eprintln!("Failed to move the end of the session: {}", e);
+                }
+                break;
+            }
+            thread::sleep(Duration::from_secs(1));
+        }
+
+        let interval = Interval {
+            phase,
+            started_at: phase_started_at,
+            ended_at,
+        };
+        if let Err(e) = commands::record_interval(app, interval) {
+            eprintln!("Failed to record Pomodoro interval: {}", e);
+        }
+        phase_started_at = ended_at;
+
+        let Some(&next) = phases.get(index + 1) else {
+            break;
+        };
+
+        if !config.block_during_breaks {
+            let result = if next.is_break() {
+                commands::lift_blocking(app).map(|_| ())
+            } else {
+                let ends_at = app
+                    .state::<SessionState>()
+                    .current()
+                    .map(|s| s.ends_at)
+                    .unwrap_or(ended_at);
+                commands::apply_blocking(app, blocked_things, blocked_apps, ends_at).map(|_| ())
+            };
+            if let Err(e) = result {
+                eprintln!("Failed to switch blocking for {}: {}", next.label(), e);
+            }
+        }
+
+        notify_transition(
+            &format!("{} time", next.label()),
+            &format!(
+                "{} for {}",
+                if next.is_break() {
+                    "Take a break"
+                } else {
+                    "Back to work"
+                },
+                format_duration(config.phase_duration(next) as i64)
+            ),
+        );
+    }
+
+    if let Err(e) = commands::end_session(app) {
+        eprintln!("Failed to end Pomodoro session: {}", e);
+    }
+    notify_transition(
+        "Pomodoro cycle complete!",
+        &format!("You finished {} focus intervals", config.work_intervals),
+    );
+}
+
+fn notify_transition(summary: &str, body: &str) {
+    notifications::notify(Notification {
+        kind: Kind::Pomodoro,
+        icon: "alarm-clock",
+        summary,
+        body,
+        actions: &[],
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(work_intervals: u32, long_break_every: u32) -> PomodoroConfig {
+        PomodoroConfig {
+            work_intervals,
+            work_duration: 1500,
+            short_break: 300,
+            long_break: 900,
+            long_break_every,
+            block_during_breaks: false,
+        }
+    }
+
+    #[test]
+    fn breaks_go_between_work_intervals() {
+        use Phase::*;
+
+        assert_eq!(config(1, 4).phases(), vec![Work]);
+        assert_eq!(
+            config(3, 4).phases(),
+            vec![Work, ShortBreak, Work, ShortBreak, Work]
+        );
+    }
+
+    #[test]
+    fn long_break_replaces_every_nth_short_break() {
+        use Phase::*;
+
+        assert_eq!(
+            config(5, 2).phases(),
+            vec![Work, ShortBreak, Work, LongBreak, Work, ShortBreak, Work, LongBreak, Work]
+        );
+        // No break follows the last interval, long or not
+        assert_eq!(
+            config(4, 4).phases(),
+            vec![Work, ShortBreak, Work, ShortBreak, Work, ShortBreak, Work]
+        );
+    }
+
+    #[test]
+    fn phase_lengths_come_from_the_config() {
+        let config = config(4, 4);
+
+        assert_eq!(config.phase_duration(Phase::Work), 1500);
+        assert_eq!(config.phase_duration(Phase::ShortBreak), 300);
+        assert_eq!(config.phase_duration(Phase::LongBreak), 900);
+    }
+
+    #[test]
+    fn total_adds_up_every_phase() {
+        assert_eq!(config(1, 4).total_duration(), 1500);
+        assert_eq!(config(4, 4).total_duration(), 4 * 1500 + 3 * 300);
+        assert_eq!(config(3, 2).total_duration(), 3 * 1500 + 300 + 900);
+    }
+
+    #[test]
+    fn plan_runs_phases_back_to_back() {
+        let config = config(2, 4);
+
+        assert_eq!(
+            config.plan(0, 1000),
+            vec![
+                PlannedPhase {
+                    index: 0,
+                    phase: Phase::Work,
+                    starts_at: 1000,
+                    ends_at: 2500,
+                },
+                PlannedPhase {
+                    index: 1,
+                    phase: Phase::ShortBreak,
+                    starts_at: 2500,
+                    ends_at: 2800,
+                },
+                PlannedPhase {
+                    index: 2,
+                    phase: Phase::Work,
+                    starts_at: 2800,
+                    ends_at: 4300,
+                },
+            ]
+        );
+        assert_eq!(
+            config.plan(0, 1000).last().unwrap().ends_at,
+            1000 + config.total_duration() as i64
+        );
+    }
+
+    #[test]
+    fn plan_picks_up_from_a_later_phase() {
+        let config = config(2, 4);
+
+        let plan = config.plan(1, 2000);
+        assert_eq!(plan.len(), 2);
+        assert_eq!((plan[0].index, plan[0].starts_at), (1, 2000));
+        assert_eq!((plan[1].index, plan[1].ends_at), (2, 3800));
+
+        assert!(config.plan(3, 2000).is_empty());
+    }
+}
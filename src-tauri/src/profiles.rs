@@ -0,0 +1,293 @@
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::block::{extract_domain, is_valid_domain};
+use crate::blocklist::{slugify, CATEGORY_PREFIX};
+use crate::reminders::ReminderSettings;
+use crate::store;
+use crate::xdg;
+
+/// How firmly a session holds once it has started.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementMode {
+    /// The session can be ended at any time.
+    #[default]
+    Standard,
+    /// Blocking cannot be lifted before the session ends.
+    Strict,
+}
+
+/// A saved session preset such as "Deep Work" or "Writing".
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: String,
+    pub name: String,
+    /// Default session length in seconds.
+    pub duration: u64,
+    /// Blocked sites in the same form sessions take them: domains, URL
+    /// rules and `category:<id>` entries.
+    #[serde(default)]
+    pub site_rules: Vec<String>,
+    /// Blocked apps encoded as `name|||executable|||icon|||source`.
+    #[serde(default)]
+    pub app_rules: Vec<String>,
+    #[serde(default)]
+    pub enforcement: EnforcementMode,
+    #[serde(default)]
+    pub reminders: ReminderSettings,
+}
+
+/// The editable part of a profile, as sent by the frontend.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfileInput {
+    pub name: String,
+    pub duration: u64,
+    #[serde(default)]
+    pub site_rules: Vec<String>,
+    #[serde(default)]
+    pub app_rules: Vec<String>,
+    #[serde(default)]
+    pub enforcement: EnforcementMode,
+    #[serde(default)]
+    pub reminders: ReminderSettings,
+}
+
+/// Focus profiles persisted in the app data directory.
+pub struct ProfileStore {
+    path: PathBuf,
+    profiles: Mutex<Vec<Profile>>,
+}
+
+impl ProfileStore {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("profiles.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let profiles = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            profiles: Mutex::new(profiles),
+        }
+    }
+
+    pub fn list(&self) -> Vec<Profile> {
+        self.profiles.lock().unwrap().clone()
+    }
+
+    pub fn get(&self, id: &str) -> Option<Profile> {
+        self.profiles
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|p| p.id == id)
+            .cloned()
+    }
+
+    pub fn create(&self, input: ProfileInput) -> Result<Profile, String> {
+        let mut profiles = self.profiles.lock().unwrap();
+
+        let base_id = slugify(input.name.trim(), "profile");
+        let mut id = base_id.clone();
+        let mut suffix = 2;
+        while profiles.iter().any(|p| p.id == id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let profile = build_profile(id, input)?;
+        profiles.push(profile.clone());
+        self.save(&profiles)?;
+
+        Ok(profile)
+    }
+
+    pub fn update(&self, id: &str, input: ProfileInput) -> Result<Profile, String> {
+        let mut profiles = self.profiles.lock().unwrap();
+
+        let existing = profiles
+            .iter_mut()
+            .find(|p| p.id == id)
+            .ok_or_else(|| format!("No profile with id {}", id))?;
+
+        let profile = build_profile(id.to_string(), input)?;
+        *existing = profile.clone();
+        self.save(&profiles)?;
+
+        Ok(profile)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut profiles = self.profiles.lock().unwrap();
+        let before = profiles.len();
+        profiles.retain(|p| p.id != id);
+
+        if profiles.len() == before {
+            return Err(format!("No profile with id {}", id));
+        }
+
+        self.save(&profiles)
+    }
+
+    fn save(&self, profiles: &[Profile]) -> Result<(), String> {
+        store::write(&self.path, profiles)
+    }
+}
+
+fn build_profile(id: String, input: ProfileInput) -> Result<Profile, String> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if input.duration == 0 {
+        return Err("Profile duration must be greater than zero".to_string());
+    }
+
+    let site_rules: Vec<String> = input
+        .site_rules
+        .iter()
+        .map(|rule| rule.trim().to_string())
+        .filter(|rule| !rule.is_empty())
+        .collect();
+
+    if let Some(invalid) = site_rules.iter().find(|rule| {
+        !rule.starts_with(CATEGORY_PREFIX) && !is_valid_domain(&extract_domain(rule))
+    }) {
+        return Err(format!("Invalid site rule: {}", invalid));
+    }
+
+    input.reminders.validate()?;
+
+    Ok(Profile {
+        id,
+        name: name.to_string(),
+        duration: input.duration,
+        site_rules,
+        app_rules: input.app_rules,
+        enforcement: input.enforcement,
+        reminders: input.reminders,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_path(test: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("brisk-profiles-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir.join("profiles.json")
+    }
+
+    fn input(name: &str) -> ProfileInput {
+        ProfileInput {
+            name: name.to_string(),
+            duration: 25 * 60,
+            site_rules: Vec::new(),
+            app_rules: Vec::new(),
+            enforcement: EnforcementMode::Standard,
+            reminders: ReminderSettings::default(),
+        }
+    }
+
+    #[test]
+    fn ids_are_slugs_of_the_name() {
+        let store = ProfileStore::load_from(store_path("slugs"));
+
+        assert_eq!(store.create(input("Deep Work")).unwrap().id, "deep-work");
+        assert_eq!(
+            store.create(input("  Writing & Email! ")).unwrap().id,
+            "writing-email"
+        );
+        assert_eq!(store.create(input("🎧")).unwrap().id, "profile");
+    }
+
+    #[test]
+    fn taken_ids_get_a_numbered_suffix() {
+        let store = ProfileStore::load_from(store_path("suffix"));
+
+        assert_eq!(store.create(input("Deep Work")).unwrap().id, "deep-work");
+        assert_eq!(store.create(input("deep work")).unwrap().id, "deep-work-2");
+        assert_eq!(store.create(input("Deep-Work")).unwrap().id, "deep-work-3");
+    }
+
+    #[test]
+    fn profiles_persist_across_loads() {
+        let path = store_path("persist");
+        let store = ProfileStore::load_from(path.clone());
+        let mut writing = input("Writing");
+        writing.site_rules = vec![" news.example.com ".to_string(), "".to_string()];
+        writing.enforcement = EnforcementMode::Strict;
+
+        let created = store.create(writing).unwrap();
+
+        assert_eq!(created.site_rules, ["news.example.com"]);
+        assert_eq!(ProfileStore::load_from(path).list(), [created]);
+    }
+
+    #[test]
+    fn update_keeps_the_id() {
+        let path = store_path("update");
+        let store = ProfileStore::load_from(path.clone());
+        store.create(input("Writing")).unwrap();
+
+        let mut renamed = input("Editing");
+        renamed.duration = 50 * 60;
+        let updated = store.update("writing", renamed).unwrap();
+
+        assert_eq!(updated.id, "writing");
+        assert_eq!(updated.name, "Editing");
+        assert_eq!(ProfileStore::load_from(path).get("writing"), Some(updated));
+        assert!(store.update("missing", input("Other")).is_err());
+    }
+
+    #[test]
+    fn delete_removes_only_that_profile() {
+        let path = store_path("delete");
+        let store = ProfileStore::load_from(path.clone());
+        store.create(input("Writing")).unwrap();
+        let reading = store.create(input("Reading")).unwrap();
+
+        store.delete("writing").unwrap();
+
+        assert_eq!(ProfileStore::load_from(path).list(), [reading]);
+        assert!(store.delete("writing").is_err());
+    }
+
+    #[test]
+    fn invalid_input_is_rejected_and_not_saved() {
+        let path = store_path("invalid");
+        let store = ProfileStore::load_from(path.clone());
+
+        let mut zero = input("Zero");
+        zero.duration = 0;
+        let mut bad_site = input("Bad site");
+        bad_site.site_rules = vec!["not a domain".to_string()];
+        let mut bad_reminder = input("Bad reminder");
+        bad_reminder.reminders.goal_interval = Some(0);
+
+        assert!(store.create(input("   ")).is_err());
+        assert!(store.create(zero).is_err());
+        assert!(store.create(bad_site).is_err());
+        assert!(store.create(bad_reminder).is_err());
+        assert!(store.list().is_empty());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn category_rules_are_accepted_as_they_are() {
+        let store = ProfileStore::load_from(store_path("categories"));
+        let mut social = input("Social");
+        social.site_rules = vec!["category:social".to_string()];
+
+        assert_eq!(
+            store.create(social).unwrap().site_rules,
+            ["category:social"]
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::notifications::{self, format_duration, Kind, Notification};
+use crate::session::{ActiveSession, SessionState};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+/// Reminders that came due longer ago than this, e.g. while Brisk was not
+/// running, are dropped instead of shown late.
+const STALE_AFTER: i64 = 60;
+
+/// What to remind of during a session. Times are in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReminderSettings {
+    /// Warnings this long before the session ends.
+    pub warnings: Vec<u64>,
+    pub halfway: bool,
+    /// Time between reminders of the session goal; none when unset.
+    pub goal_interval: Option<u64>,
+}
+
+impl Default for ReminderSettings {
+    fn default() -> Self {
+        Self {
+            warnings: vec![5 * 60],
+            halfway: true,
+            goal_interval: None,
+        }
+    }
+}
+
+impl ReminderSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.warnings.contains(&0) {
+            return Err("Warnings must come before the session ends".to_string());
+        }
+        if self.goal_interval == Some(0) {
+            return Err("Goal reminder interval must be greater than zero".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Reminder {
+    Warning { before: u64 },
+    Halfway,
+    Goal { index: u64 },
+}
+
+/// Payload of the `session-reminder` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReminderEvent {
+    #[serde(flatten)]
+    pub reminder: Reminder,
+    pub goal: String,
+    /// Seconds until the session ends.
+    pub remaining: i64,
+    pub message: String,
+}
+
+/// When each reminder of `session` comes due.
+fn schedule(session: &ActiveSession) -> Vec<(i64, Reminder)> {
+    let settings = &session.reminders;
+    let length = session.ends_at - session.started_at;
+    let mut due = Vec::new();
+
+    for &before in &settings.warnings {
+        if (before as i64) < length {
+            due.push((
+                session.ends_at - before as i64,
+                Reminder::Warning { before },
+            ));
+        }
+    }
+    if settings.halfway {
+        due.push((session.started_at + length / 2, Reminder::Halfway));
+    }
+    if let Some(interval) = settings.goal_interval {
+        let count = (length.max(1) as u64 - 1) / interval;
+        for index in 1..=count {
+            due.push((
+                session.started_at + (index * interval) as i64,
+                Reminder::Goal { index },
+            ));
+        }
+    }
+
+    due
+}
+
+/// Which reminders of the active session were sent, so each goes out once
+/// and those that come due during a pause go out when it ends.
+#[derive(Debug, Default)]
+struct Tracker {
+    /// Start of the session the rest belongs to.
+    started_at: i64,
+    fired: Vec<Reminder>,
+    /// When the session was last seen paused.
+    paused_at: Option<i64>,
+}
+
+impl Tracker {
+    /// Reminders of `session` to send at `now`.
+    fn due(&mut self, session: &ActiveSession, now: i64) -> Vec<Reminder> {
+        if self.started_at != session.started_at {
+            *self = Tracker {
+                started_at: session.started_at,
+                ..Tracker::default()
+            };
+        }
+
+        // Nothing is blocked during a pause, so there is nothing to keep
+        // focused on; reminders that come due are held until it ends
+        if session.pause.is_some() {
+            self.paused_at = Some(now);
+            return Vec::new();
+        }
+
+        let mut reminders = Vec::new();
+        for (due, reminder) in schedule(session) {
+            if due > now || self.fired.contains(&reminder) {
+                continue;
+            }
+            self.fired.push(reminder);
+
+            // A reminder held back by a pause is due when it ends
+            let due = self.paused_at.map_or(due, |paused| due.max(paused));
+            if now - due <= STALE_AFTER {
+                reminders.push(reminder);
+            }
+        }
+        reminders
+    }
+}
+
+/// Watches the active session and sends its reminders as
+/// `session-reminder` events and desktop notifications.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || {
+        let mut tracker = Tracker::default();
+
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let Some(session) = app.state::<SessionState>().current() else {
+                continue;
+            };
+
+            let now = Utc::now().timestamp();
+            for reminder in tracker.due(&session, now) {
+                remind(&app, &session, reminder, now);
+            }
+        }
+    });
+}
+
+fn remind(app: &AppHandle, session: &ActiveSession, reminder: Reminder, now: i64) {
+    let remaining = (session.ends_at - now).max(0);
+    let (summary, message) = match reminder {
+        Reminder::Warning { .. } => (
+            format!("{} left", format_duration(remaining)),
+            format!("{} is almost over", session.goal),
+        ),
+        Reminder::Halfway => (
+            "Halfway there".to_string(),
+            format!("{} left on {}", format_duration(remaining), session.goal),
+        ),
+        Reminder::Goal { .. } => ("Stay on track".to_string(), session.goal.clone()),
+    };
+
+    let _ = app.emit(
+        "session-reminder",
+        &ReminderEvent {
+            reminder,
+            goal: session.goal.clone(),
+            remaining,
+            message: format!("{}: {}", summary, message),
+        },
+    );
+    notifications::notify(Notification {
+        kind: Kind::Reminder,
+        icon: "appointment-soon",
+        summary: &summary,
+        body: &message,
+        actions: &[],
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::EnforcementMode;
+    use crate::session::ActivePause;
+
+    /// A 25-minute session starting at 1000, ending at 2500.
+    fn session(reminders: ReminderSettings) -> ActiveSession {
+        ActiveSession {
+            goal: "Write".to_string(),
+            started_at: 1000,
+            ends_at: 2500,
+            profile: None,
+            enforcement: EnforcementMode::Standard,
+            schedule: None,
+            record: "session.json".into(),
+            blocked_things: Vec::new(),
+            blocked_apps: Vec::new(),
+            pause: None,
+            paused_for: 0,
+            pause_budget: 0,
+            unlocked: false,
+            reminders,
+        }
+    }
+
+    fn paused(mut session: ActiveSession, started_at: i64) -> ActiveSession {
+        session.pause = Some(ActivePause {
+            started_at,
+            resumes_at: started_at + 300,
+        });
+        session
+    }
+
+    #[test]
+    fn default_reminders_warn_five_minutes_before_and_at_halfway() {
+        assert_eq!(
+            schedule(&session(ReminderSettings::default())),
+            [
+                (2200, Reminder::Warning { before: 300 }),
+                (1750, Reminder::Halfway),
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings_longer_than_the_session_are_left_out() {
+        let settings = ReminderSettings {
+            warnings: vec![60, 1500, 3600],
+            halfway: false,
+            goal_interval: None,
+        };
+
+        assert_eq!(
+            schedule(&session(settings)),
+            [(2440, Reminder::Warning { before: 60 })]
+        );
+    }
+
+    #[test]
+    fn goal_reminders_repeat_until_the_end() {
+        let settings = ReminderSettings {
+            warnings: Vec::new(),
+            halfway: false,
+            goal_interval: Some(500),
+        };
+
+        // The third would coincide with the end of the session
+        assert_eq!(
+            schedule(&session(settings)),
+            [
+                (1500, Reminder::Goal { index: 1 }),
+                (2000, Reminder::Goal { index: 2 }),
+            ]
+        );
+    }
+
+    #[test]
+    fn reminders_fire_once_when_due() {
+        let session = session(ReminderSettings::default());
+        let mut tracker = Tracker::default();
+
+        assert!(tracker.due(&session, 1749).is_empty());
+        assert_eq!(tracker.due(&session, 1750), [Reminder::Halfway]);
+        assert!(tracker.due(&session, 1751).is_empty());
+        assert_eq!(
+            tracker.due(&session, 2201),
+            [Reminder::Warning { before: 300 }]
+        );
+        assert!(tracker.due(&session, 2202).is_empty());
+    }
+
+    #[test]
+    fn stale_reminders_are_dropped() {
+        let session = session(ReminderSettings::default());
+        let mut tracker = Tracker::default();
+
+        // Brisk was not running at halfway
+        assert!(tracker.due(&session, 1750 + STALE_AFTER + 1).is_empty());
+        assert_eq!(
+            tracker.due(&session, 2200),
+            [Reminder::Warning { before: 300 }]
+        );
+    }
+
+    #[test]
+    fn reminders_due_during_a_pause_wait_for_it_to_end() {
+        let running = session(ReminderSettings::default());
+        let mut tracker = Tracker::default();
+
+        assert!(tracker.due(&running, 1700).is_empty());
+        for now in [1740, 1750, 1800, 1900] {
+            assert!(tracker.due(&paused(running.clone(), 1740), now).is_empty());
+        }
+
+        // Halfway came due 150 seconds ago, but the pause only just ended
+        assert_eq!(tracker.due(&running, 1901), [Reminder::Halfway]);
+    }
+
+    #[test]
+    fn a_new_session_starts_over() {
+        let first = session(ReminderSettings::default());
+        let mut second = first.clone();
+        second.started_at = 2600;
+        second.ends_at = 4100;
+        let mut tracker = Tracker::default();
+
+        assert_eq!(tracker.due(&first, 1750), [Reminder::Halfway]);
+        assert_eq!(tracker.due(&second, 3350), [Reminder::Halfway]);
+    }
+}
@@ -0,0 +1,574 @@
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Datelike, Local, LocalResult, NaiveDate, NaiveTime, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::blocklist::slugify;
+use crate::commands::{self, SessionRequest};
+use crate::profiles::ProfileStore;
+use crate::session::SessionState;
+use crate::store;
+use crate::xdg;
+
+/// Short enough that a window missed during suspend starts soon after wake.
+const TICK_INTERVAL: Duration = Duration::from_secs(15);
+const DST_GAP_STEP_MINUTES: i64 = 15;
+
+/// Source of the current time, so schedule decisions can be driven by a
+/// fake clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Local>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A weekly recurring focus window, e.g. weekdays 09:00–12:00, that starts
+/// a session from a profile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub id: String,
+    pub name: String,
+    pub profile: String,
+    pub days: Vec<Weekday>,
+    /// Local wall-clock times. A window whose end is not after its start
+    /// runs past midnight into the next day.
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+    /// Start of the last window that started a session, so ending a
+    /// scheduled session early does not restart it on the next tick.
+    #[serde(default)]
+    pub last_triggered: Option<i64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduleInput {
+    pub name: String,
+    pub profile: String,
+    pub days: Vec<Weekday>,
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// A schedule window that should start a session now.
+#[derive(Debug, Clone)]
+pub struct Trigger {
+    pub schedule: Schedule,
+    pub starts_at: DateTime<Local>,
+    pub ends_at: DateTime<Local>,
+}
+
+impl Schedule {
+    /// The window of this schedule that contains `now`, if any.
+    pub fn window_containing<Tz: TimeZone>(
+        &self,
+        now: &DateTime<Tz>,
+    ) -> Option<(DateTime<Tz>, DateTime<Tz>)> {
+        let tz = now.timezone();
+        let today = now.date_naive();
+
+        // Yesterday's window may still be open if it runs past midnight
+        [today.pred_opt(), Some(today)]
+            .into_iter()
+            .flatten()
+            .filter(|day| self.days.contains(&day.weekday()))
+            .filter_map(|day| {
+                let end_day = if self.end <= self.start {
+                    day.succ_opt()?
+                } else {
+                    day
+                };
+                Some((
+                    resolve_local(&tz, day, self.start),
+                    resolve_local(&tz, end_day, self.end),
+                ))
+            })
+            .find(|(start, end)| start <= now && now < end)
+    }
+}
+
+/// Maps a wall-clock time to an instant across DST changes: a time skipped
+/// when clocks spring forward moves to the first valid time after the gap,
+/// and a time repeated when they fall back uses its first occurrence.
+fn resolve_local<Tz: TimeZone>(tz: &Tz, date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    let mut local = date.and_time(time);
+
+    for _ in 0..(24 * 60 / DST_GAP_STEP_MINUTES) {
+        match tz.from_local_datetime(&local) {
+            LocalResult::Single(t) => return t,
+            LocalResult::Ambiguous(earliest, _) => return earliest,
+            LocalResult::None => local += chrono::Duration::minutes(DST_GAP_STEP_MINUTES),
+        }
+    }
+
+    tz.from_utc_datetime(&date.and_time(time))
+}
+
+/// Recurring schedules persisted in the app data directory.
+pub struct Scheduler {
+    path: PathBuf,
+    schedules: Mutex<Vec<Schedule>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Scheduler {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("schedules.json"), Arc::new(SystemClock))
+    }
+
+    pub fn load_from(path: PathBuf, clock: Arc<dyn Clock>) -> Self {
+        let schedules = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            schedules: Mutex::new(schedules),
+            clock,
+        }
+    }
+
+    pub fn list(&self) -> Vec<Schedule> {
+        self.schedules.lock().unwrap().clone()
+    }
+
+    pub fn create(&self, input: ScheduleInput) -> Result<Schedule, String> {
+        let mut schedules = self.schedules.lock().unwrap();
+
+        let base_id = slugify(input.name.trim(), "schedule");
+        let mut id = base_id.clone();
+        let mut suffix = 2;
+        while schedules.iter().any(|s| s.id == id) {
+            id = format!("{}-{}", base_id, suffix);
+            suffix += 1;
+        }
+
+        let schedule = build_schedule(id, input, None)?;
+        schedules.push(schedule.clone());
+        self.save(&schedules)?;
+
+        Ok(schedule)
+    }
+
+    pub fn update(&self, id: &str, input: ScheduleInput) -> Result<Schedule, String> {
+        let mut schedules = self.schedules.lock().unwrap();
+
+        let existing = schedules
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| format!("No schedule with id {}", id))?;
+
+        let schedule = build_schedule(id.to_string(), input, existing.last_triggered)?;
+        *existing = schedule.clone();
+        self.save(&schedules)?;
+
+        Ok(schedule)
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let before = schedules.len();
+        schedules.retain(|s| s.id != id);
+
+        if schedules.len() == before {
+            return Err(format!("No schedule with id {}", id));
+        }
+
+        self.save(&schedules)
+    }
+
+    /// Windows that are open now and have not started a session yet. A
+    /// window missed while the machine was suspended or Brisk was closed is
+    /// still due for whatever is left of it.
+    pub fn due(&self) -> Vec<Trigger> {
+        let now = self.clock.now();
+
+        self.schedules
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|s| s.enabled)
+            .filter_map(|s| {
+                let (starts_at, ends_at) = s.window_containing(&now)?;
+                (s.last_triggered != Some(starts_at.timestamp())).then(|| Trigger {
+                    schedule: s.clone(),
+                    starts_at,
+                    ends_at,
+                })
+            })
+            .collect()
+    }
+
+    pub fn mark_triggered(&self, id: &str, starts_at: i64) -> Result<(), String> {
+        let mut schedules = self.schedules.lock().unwrap();
+        if let Some(schedule) = schedules.iter_mut().find(|s| s.id == id) {
+            schedule.last_triggered = Some(starts_at);
+        }
+        self.save(&schedules)
+    }
+
+    fn save(&self, schedules: &[Schedule]) -> Result<(), String> {
+        store::write(&self.path, schedules)
+    }
+}
+
+fn build_schedule(
+    id: String,
+    input: ScheduleInput,
+    last_triggered: Option<i64>,
+) -> Result<Schedule, String> {
+    let name = input.name.trim();
+    if name.is_empty() {
+        return Err("Schedule name cannot be empty".to_string());
+    }
+    if input.days.is_empty() {
+        return Err("Schedule needs at least one day".to_string());
+    }
+    if input.start == input.end {
+        return Err("Schedule start and end cannot be the same time".to_string());
+    }
+
+    let mut days = input.days;
+    days.sort_by_key(|d| d.num_days_from_monday());
+    days.dedup();
+
+    Ok(Schedule {
+        id,
+        name: name.to_string(),
+        profile: input.profile,
+        days,
+        start: input.start,
+        end: input.end,
+        enabled: input.enabled,
+        last_triggered,
+    })
+}
+
+/// Checks the schedules periodically in the background and starts due
+/// sessions, which end at the close of their window.
+pub fn start(app: AppHandle) {
+    thread::spawn(move || loop {
+        tick(&app);
+        thread::sleep(TICK_INTERVAL);
+    });
+}
+
+fn tick(app: &AppHandle) {
+    let scheduler = app.state::<Scheduler>();
+
+    // Schedules never interrupt a running session, which ends on its own
+    // at its deadline
+    if app.state::<SessionState>().current().is_some() {
+        return;
+    }
+
+    let Some(trigger) = scheduler.due().into_iter().next() else {
+        return;
+    };
+    let schedule = trigger.schedule;
+
+    // Mark first so a failing start is not retried on every tick
+    if let Err(e) = scheduler.mark_triggered(&schedule.id, trigger.starts_at.timestamp()) {
+        eprintln!("Failed to save schedule {}: {}", schedule.id, e);
+    }
+
+    let Some(profile) = app.state::<ProfileStore>().get(&schedule.profile) else {
+        eprintln!(
+            "Schedule {} refers to missing profile {}",
+            schedule.name, schedule.profile
+        );
+        return;
+    };
+
+    let remaining = trigger.ends_at.timestamp() - scheduler.clock.now().timestamp();
+    let mut request = SessionRequest::from_profile(profile);
+    request.duration = remaining.max(1) as u64;
+    request.schedule = Some(schedule.id.clone());
+
+    match commands::start_session(app, request) {
+        Ok(message) => println!("Schedule {} started a session: {}", schedule.name, message),
+        Err(e) => eprintln!("Schedule {} failed to start a session: {}", schedule.name, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use chrono::{FixedOffset, NaiveDateTime, Offset};
+
+    struct FakeClock(Mutex<DateTime<Local>>);
+
+    impl FakeClock {
+        fn set(&self, now: DateTime<Local>) {
+            *self.0.lock().unwrap() = now;
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> DateTime<Local> {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// Central European time with the 2025 transitions: clocks spring
+    /// forward from 02:00 to 03:00 on 30 March and fall back from 03:00 to
+    /// 02:00 on 26 October.
+    #[derive(Debug, Clone, Copy)]
+    struct Cet;
+
+    impl Cet {
+        fn winter() -> FixedOffset {
+            FixedOffset::east_opt(3600).unwrap()
+        }
+
+        fn summer() -> FixedOffset {
+            FixedOffset::east_opt(2 * 3600).unwrap()
+        }
+    }
+
+    impl TimeZone for Cet {
+        type Offset = FixedOffset;
+
+        fn from_offset(_offset: &FixedOffset) -> Self {
+            Cet
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            // Earliest instant first, as chrono expects
+            let offsets: Vec<FixedOffset> = [Cet::summer(), Cet::winter()]
+                .into_iter()
+                .filter(|offset| {
+                    let utc =
+                        *local - chrono::Duration::seconds(offset.fix().local_minus_utc() as i64);
+                    self.offset_from_utc_datetime(&utc) == *offset
+                })
+                .collect();
+
+            match offsets[..] {
+                [] => LocalResult::None,
+                [offset] => LocalResult::Single(offset),
+                [earliest, latest, ..] => LocalResult::Ambiguous(earliest, latest),
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_time(NaiveTime::MIN))
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer_starts = date(2025, 3, 30).and_hms_opt(1, 0, 0).unwrap();
+            let summer_ends = date(2025, 10, 26).and_hms_opt(1, 0, 0).unwrap();
+            if (summer_starts..summer_ends).contains(utc) {
+                Cet::summer()
+            } else {
+                Cet::winter()
+            }
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    /// A local time in January, which no time zone changes clocks in.
+    fn january(day: u32, hour: u32, minute: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2025, 1, day, hour, minute, 0)
+            .single()
+            .unwrap()
+    }
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> i64 {
+        date(year, month, day)
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+            .and_utc()
+            .timestamp()
+    }
+
+    fn schedule(days: &[Weekday], start: NaiveTime, end: NaiveTime) -> Schedule {
+        Schedule {
+            id: "mornings".to_string(),
+            name: "Mornings".to_string(),
+            profile: "work".to_string(),
+            days: days.to_vec(),
+            start,
+            end,
+            enabled: true,
+            last_triggered: None,
+        }
+    }
+
+    fn scheduler(
+        test: &str,
+        now: DateTime<Local>,
+        schedules: &[Schedule],
+    ) -> (Scheduler, Arc<FakeClock>) {
+        let dir =
+            std::env::temp_dir().join(format!("brisk-schedule-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        let path = dir.join("schedules.json");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&path, serde_json::to_string(schedules).unwrap()).unwrap();
+
+        let clock = Arc::new(FakeClock(Mutex::new(now)));
+        (Scheduler::load_from(path, clock.clone()), clock)
+    }
+
+    // 13 January 2025 is a Monday
+
+    #[test]
+    fn window_is_due_only_while_open() {
+        let mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        let (scheduler, clock) = scheduler("open", january(13, 8, 59), &[mornings]);
+        assert!(scheduler.due().is_empty());
+
+        clock.set(january(13, 9, 0));
+        let due = scheduler.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].starts_at, january(13, 9, 0));
+        assert_eq!(due[0].ends_at, january(13, 12, 0));
+
+        clock.set(january(13, 12, 0));
+        assert!(scheduler.due().is_empty());
+    }
+
+    #[test]
+    fn window_is_only_due_on_its_days() {
+        let mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        let (scheduler, _) = scheduler("days", january(14, 10, 0), &[mornings]);
+
+        assert!(scheduler.due().is_empty());
+    }
+
+    #[test]
+    fn missed_tick_is_still_due_for_the_rest_of_the_window() {
+        let mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        // As if the machine was suspended from before 09:00 until 11:30
+        let (scheduler, _) = scheduler("missed", january(13, 11, 30), &[mornings]);
+
+        let due = scheduler.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].starts_at, january(13, 9, 0));
+    }
+
+    #[test]
+    fn triggered_window_is_not_due_again_until_next_week() {
+        let mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        let (scheduler, clock) = scheduler("triggered", january(13, 9, 0), &[mornings]);
+
+        let starts_at = scheduler.due()[0].starts_at.timestamp();
+        scheduler.mark_triggered("mornings", starts_at).unwrap();
+
+        clock.set(january(13, 10, 0));
+        assert!(scheduler.due().is_empty());
+
+        clock.set(january(20, 9, 30));
+        assert_eq!(scheduler.due().len(), 1);
+    }
+
+    #[test]
+    fn triggered_window_stays_triggered_after_reload() {
+        let mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        let (scheduler, clock) = scheduler("reload", january(13, 9, 0), &[mornings]);
+        let starts_at = scheduler.due()[0].starts_at.timestamp();
+        scheduler.mark_triggered("mornings", starts_at).unwrap();
+
+        let reloaded = Scheduler::load_from(scheduler.path.clone(), clock);
+        assert!(reloaded.due().is_empty());
+    }
+
+    #[test]
+    fn disabled_schedule_is_never_due() {
+        let mut mornings = schedule(&[Weekday::Mon], time(9, 0), time(12, 0));
+        mornings.enabled = false;
+        let (scheduler, _) = scheduler("disabled", january(13, 10, 0), &[mornings]);
+
+        assert!(scheduler.due().is_empty());
+    }
+
+    #[test]
+    fn overnight_window_is_due_after_midnight() {
+        let nights = schedule(&[Weekday::Mon], time(22, 0), time(2, 0));
+        let (scheduler, clock) = scheduler("overnight", january(14, 1, 0), &[nights]);
+
+        let due = scheduler.due();
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].starts_at, january(13, 22, 0));
+        assert_eq!(due[0].ends_at, january(14, 2, 0));
+
+        // Tuesday's own night is not scheduled
+        clock.set(january(14, 22, 30));
+        assert!(scheduler.due().is_empty());
+    }
+
+    #[test]
+    fn time_skipped_by_dst_moves_past_the_gap() {
+        let resolved = resolve_local(&Cet, date(2025, 3, 30), time(2, 30));
+
+        assert_eq!(resolved.timestamp(), utc(2025, 3, 30, 1, 0));
+        assert_eq!(
+            resolved.naive_local(),
+            date(2025, 3, 30).and_time(time(3, 0))
+        );
+    }
+
+    #[test]
+    fn time_repeated_by_dst_uses_its_first_occurrence() {
+        let resolved = resolve_local(&Cet, date(2025, 10, 26), time(2, 30));
+
+        assert_eq!(resolved.timestamp(), utc(2025, 10, 26, 0, 30));
+    }
+
+    #[test]
+    fn window_starting_in_dst_gap_opens_after_it() {
+        // 30 March 2025 is a Sunday
+        let early = schedule(&[Weekday::Sun], time(2, 30), time(4, 0));
+
+        let before = Cet.timestamp_opt(utc(2025, 3, 30, 0, 59), 0).unwrap();
+        assert!(early.window_containing(&before).is_none());
+
+        let after = Cet.timestamp_opt(utc(2025, 3, 30, 1, 15), 0).unwrap();
+        let (starts_at, ends_at) = early.window_containing(&after).unwrap();
+        assert_eq!(starts_at.timestamp(), utc(2025, 3, 30, 1, 0));
+        assert_eq!(ends_at.timestamp(), utc(2025, 3, 30, 2, 0));
+    }
+
+    #[test]
+    fn window_ending_in_repeated_hour_closes_at_first_occurrence() {
+        // 26 October 2025 is a Sunday
+        let late = schedule(&[Weekday::Sun], time(1, 0), time(2, 30));
+
+        // 02:15 the first time round
+        let first = Cet.timestamp_opt(utc(2025, 10, 26, 0, 15), 0).unwrap();
+        assert!(late.window_containing(&first).is_some());
+
+        // 02:15 again, after clocks went back
+        let second = Cet.timestamp_opt(utc(2025, 10, 26, 1, 15), 0).unwrap();
+        assert!(late.window_containing(&second).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::profiles::EnforcementMode;
+use crate::reminders::ReminderSettings;
+use crate::store;
+use crate::xdg;
+
+const CHALLENGE_LENGTH: usize = 64;
+/// Letters and digits that are hard to confuse when typed by hand.
+const CHALLENGE_ALPHABET: &[u8] = b"abcdefghjkmnpqrstuvwxyz23456789";
+
+/// The session currently blocking sites and apps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveSession {
+    pub goal: String,
+    pub started_at: i64,
+    pub ends_at: i64,
+    pub profile: Option<String>,
+    pub enforcement: EnforcementMode,
+    /// The schedule that started the session, if it was not started by hand.
+    pub schedule: Option<String>,
+    /// The history file the session is recorded in.
+    pub record: PathBuf,
+    /// Kept so blocking can be re-applied after a pause or a restart.
+    #[serde(default)]
+    pub blocked_things: Vec<String>,
+    #[serde(default)]
+    pub blocked_apps: Vec<String>,
+    /// Set while blocking is lifted for a pause.
+    pub pause: Option<ActivePause>,
+    /// Seconds of the pause budget spent on finished pauses.
+    pub paused_for: u64,
+    pub pause_budget: u64,
+    /// Set once the unlock challenge of a strict session is completed.
+    #[serde(default)]
+    pub unlocked: bool,
+    #[serde(default)]
+    pub reminders: ReminderSettings,
+}
+
+impl ActiveSession {
+    /// Whether strict mode still holds the session at `now`.
+    pub fn is_locked(&self, now: i64) -> bool {
+        self.enforcement == EnforcementMode::Strict && !self.unlocked && now < self.ends_at
+    }
+
+    pub fn remaining_pause_budget(&self) -> u64 {
+        self.pause_budget.saturating_sub(self.paused_for)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivePause {
+    pub started_at: i64,
+    /// When blocking comes back on its own.
+    pub resumes_at: i64,
+}
+
+/// A finished pause, recorded in the session history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pause {
+    pub started_at: i64,
+    pub ended_at: i64,
+}
+
+/// The active session, persisted in the app data directory so it
+/// survives a restart of Brisk.
+pub struct SessionState {
+    path: PathBuf,
+    current: Mutex<Option<ActiveSession>>,
+    challenge: Mutex<Option<String>>,
+}
+
+impl SessionState {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("active_session.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let current = store::read(&path);
+
+        Self {
+            path,
+            current: Mutex::new(current),
+            challenge: Mutex::new(None),
+        }
+    }
+
+    pub fn current(&self) -> Option<ActiveSession> {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Makes `session` the active one. Refuses while another session is
+    /// running, so a locked strict session cannot be swapped for one that
+    /// is easier to end.
+    pub fn begin(&self, session: ActiveSession) -> Result<(), String> {
+        let mut current = self.current.lock().unwrap();
+        if current.is_some() {
+            return Err("A session is already running".to_string());
+        }
+        *current = Some(session);
+        self.save(&current);
+        *self.challenge.lock().unwrap() = None;
+        Ok(())
+    }
+
+    /// Refuses while a strict session is running and has not been unlocked.
+    pub fn ensure_unlocked(&self, now: i64) -> Result<(), String> {
+        match self.current() {
+            Some(session) if session.is_locked(now) => Err(format!(
+                "Strict mode is on until the session ends in {} minutes. \
+                 Complete the unlock challenge to end it early.",
+                (session.ends_at - now + 59) / 60
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Creates the random text that has to be typed back to unlock a strict
+    /// session early.
+    pub fn new_challenge(&self, now: i64) -> Result<String, String> {
+        let session = self.current().ok_or("No active session")?;
+        if !session.is_locked(now) {
+            return Err("The session is not locked".to_string());
+        }
+
+        let challenge = random_challenge()?;
+        *self.challenge.lock().unwrap() = Some(challenge.clone());
+        Ok(challenge)
+    }
+
+    /// Unlocks the session if `response` matches the last challenge. A wrong
+    /// response uses up the challenge so a new one has to be requested.
+    pub fn complete_challenge(&self, response: &str) -> Result<ActiveSession, String> {
+        let challenge = self
+            .challenge
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or("Request an unlock challenge first")?;
+        if response.trim() != challenge {
+            return Err("The text does not match the challenge".to_string());
+        }
+
+        let mut current = self.current.lock().unwrap();
+        let session = current.as_mut().ok_or("No active session")?;
+        session.unlocked = true;
+        let unlocked = session.clone();
+        self.save(&current);
+
+        Ok(unlocked)
+    }
+
+    /// Marks the session paused until `resumes_at`.
+    pub fn pause(&self, started_at: i64, resumes_at: i64) -> Result<ActiveSession, String> {
+        let mut current = self.current.lock().unwrap();
+        let session = current.as_mut().ok_or("No active session")?;
+
+        if session.pause.is_some() {
+            return Err("The session is already paused".to_string());
+        }
+        session.pause = Some(ActivePause {
+            started_at,
+            resumes_at,
+        });
+        let paused = session.clone();
+        self.save(&current);
+
+        Ok(paused)
+    }
+
+    /// Ends the current pause and charges its length to the budget.
+    pub fn resume(&self, now: i64) -> Result<(ActiveSession, Pause), String> {
+        let mut current = self.current.lock().unwrap();
+        let session = current.as_mut().ok_or("No active session")?;
+        let pause = session.pause.take().ok_or("The session is not paused")?;
+
+        let ended_at = now.min(pause.resumes_at).max(pause.started_at);
+        session.paused_for += (ended_at - pause.started_at) as u64;
+        let resumed = session.clone();
+        self.save(&current);
+
+        Ok((
+            resumed,
+            Pause {
+                started_at: pause.started_at,
+                ended_at,
+            },
+        ))
+    }
+
+    /// Moves the end of the session by `by` seconds, earlier when negative.
+    pub fn move_end(&self, by: i64) -> Result<ActiveSession, String> {
+        let mut current = self.current.lock().unwrap();
+        let session = current.as_mut().ok_or("No active session")?;
+        session.ends_at += by;
+        let moved = session.clone();
+        self.save(&current);
+
+        Ok(moved)
+    }
+
+    pub fn finish(&self) -> Option<ActiveSession> {
+        let mut current = self.current.lock().unwrap();
+        let finished = current.take();
+        self.save(&current);
+        *self.challenge.lock().unwrap() = None;
+        finished
+    }
+
+    fn save(&self, current: &Option<ActiveSession>) {
+        let result = match current {
+            Some(session) => self.write(session),
+            None if self.path.exists() => {
+                fs::remove_file(&self.path).map_err(|e| format!("Failed to remove: {}", e))
+            }
+            None => Ok(()),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Failed to persist the active session: {}", e);
+        }
+    }
+
+    fn write(&self, session: &ActiveSession) -> Result<(), String> {
+        store::write(&self.path, session)
+    }
+}
+
+fn random_challenge() -> Result<String, String> {
+    let mut bytes = [0u8; CHALLENGE_LENGTH];
+    File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut bytes))
+        .map_err(|e| format!("Failed to generate challenge: {}", e))?;
+
+    Ok(bytes
+        .iter()
+        .map(|b| CHALLENGE_ALPHABET[*b as usize % CHALLENGE_ALPHABET.len()] as char)
+        .collect())
+}
@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+use crate::commands::{self, SessionRequest};
+use crate::profiles::ProfileStore;
+use crate::session::SessionState;
+use crate::store;
+use crate::windows::{self, WindowKind};
+use crate::xdg;
+
+/// Shown instead of registering anything on Wayland, where the shortcut
+/// plugin's X11 key grabs only see keys pressed in X11 windows.
+const WAYLAND_UNSUPPORTED: &str = "Global shortcuts are not supported on Wayland. \
+    Bind `brisk start --profile <id>` in your desktop's keyboard settings instead.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    ToggleLauncher,
+    StartDefaultProfile,
+    ToggleWidget,
+}
+
+/// Global key bindings in the form the shortcut plugin parses, e.g.
+/// `Ctrl+Alt+Space`. A binding left empty is not registered.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShortcutSettings {
+    #[serde(default)]
+    pub toggle_launcher: Option<String>,
+    #[serde(default)]
+    pub start_default_profile: Option<String>,
+    #[serde(default)]
+    pub toggle_widget: Option<String>,
+    /// Profile started by `start_default_profile`, falling back to the
+    /// first profile.
+    #[serde(default)]
+    pub default_profile: Option<String>,
+}
+
+impl Default for ShortcutSettings {
+    fn default() -> Self {
+        Self {
+            toggle_launcher: Some("Ctrl+Alt+Space".to_string()),
+            start_default_profile: Some("Ctrl+Alt+S".to_string()),
+            toggle_widget: Some("Ctrl+Alt+W".to_string()),
+            default_profile: None,
+        }
+    }
+}
+
+impl ShortcutSettings {
+    fn bindings(&self) -> Vec<(Action, &str)> {
+        [
+            (Action::ToggleLauncher, &self.toggle_launcher),
+            (Action::StartDefaultProfile, &self.start_default_profile),
+            (Action::ToggleWidget, &self.toggle_widget),
+        ]
+        .into_iter()
+        .filter_map(|(action, binding)| {
+            binding
+                .as_deref()
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(|b| (action, b))
+        })
+        .collect()
+    }
+}
+
+/// A binding that could not be registered, usually because another
+/// application already grabbed the keys.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutConflict {
+    pub action: Action,
+    pub shortcut: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShortcutStatus {
+    pub settings: ShortcutSettings,
+    pub conflicts: Vec<ShortcutConflict>,
+    /// Why no shortcut is registered at all in this session, if so.
+    pub unsupported: Option<String>,
+}
+
+/// Shortcut settings persisted in the app data directory.
+pub struct ShortcutStore {
+    path: PathBuf,
+    settings: Mutex<ShortcutSettings>,
+    conflicts: Mutex<Vec<ShortcutConflict>>,
+}
+
+impl ShortcutStore {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("shortcuts.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let settings = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            settings: Mutex::new(settings),
+            conflicts: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn status(&self) -> ShortcutStatus {
+        ShortcutStatus {
+            settings: self.settings.lock().unwrap().clone(),
+            conflicts: self.conflicts.lock().unwrap().clone(),
+            unsupported: unsupported(),
+        }
+    }
+
+    /// Falls back to the first profile once `id`, the default, is deleted.
+    pub fn forget_profile(&self, id: &str) -> Result<(), String> {
+        let mut settings = self.settings.lock().unwrap();
+        if settings.default_profile.as_deref() != Some(id) {
+            return Ok(());
+        }
+
+        let mut updated = settings.clone();
+        updated.default_profile = None;
+        self.save(&updated)?;
+        *settings = updated;
+        Ok(())
+    }
+
+    fn action_for(&self, shortcut: &Shortcut) -> Option<Action> {
+        self.settings
+            .lock()
+            .unwrap()
+            .bindings()
+            .into_iter()
+            .find(|(_, binding)| {
+                Shortcut::from_str(binding).is_ok_and(|parsed| parsed.id() == shortcut.id())
+            })
+            .map(|(action, _)| action)
+    }
+
+    fn save(&self, settings: &ShortcutSettings) -> Result<(), String> {
+        store::write(&self.path, settings)
+    }
+}
+
+/// Registers the stored bindings, recording the ones that conflict and
+/// telling the frontend about them.
+pub fn register_saved(app: &AppHandle) {
+    if let Some(reason) = unsupported() {
+        eprintln!("{}", reason);
+        let _ = app.emit("shortcuts-unsupported", &reason);
+        return;
+    }
+
+    let settings = app.state::<ShortcutStore>().status().settings;
+    let conflicts = register(app, &settings);
+
+    if !conflicts.is_empty() {
+        let _ = app.emit("shortcut-conflicts", &conflicts);
+    }
+    *app.state::<ShortcutStore>().conflicts.lock().unwrap() = conflicts;
+}
+
+/// Validates and registers new bindings, then persists them. Bindings that
+/// cannot be parsed or are used twice are rejected before anything
+/// changes; keys grabbed by other applications come back as conflicts.
+pub fn update(app: &AppHandle, settings: ShortcutSettings) -> Result<ShortcutStatus, String> {
+    let mut seen: Vec<(u32, Action)> = Vec::new();
+    for (action, binding) in settings.bindings() {
+        let shortcut = Shortcut::from_str(binding)
+            .map_err(|e| format!("Invalid shortcut {}: {}", binding, e))?;
+        if let Some((_, other)) = seen.iter().find(|(id, _)| *id == shortcut.id()) {
+            return Err(format!(
+                "{} is bound to both {:?} and {:?}",
+                binding, other, action
+            ));
+        }
+        seen.push((shortcut.id(), action));
+    }
+
+    let store = app.state::<ShortcutStore>();
+    store.save(&settings)?;
+    *store.settings.lock().unwrap() = settings.clone();
+
+    let unsupported = unsupported();
+    let conflicts = match unsupported {
+        Some(_) => Vec::new(),
+        None => register(app, &settings),
+    };
+    *store.conflicts.lock().unwrap() = conflicts.clone();
+
+    Ok(ShortcutStatus {
+        settings,
+        conflicts,
+        unsupported,
+    })
+}
+
+/// Why global shortcuts cannot work in this session, or `None` when they
+/// can.
+fn unsupported() -> Option<String> {
+    let wayland = std::env::var("WAYLAND_DISPLAY").is_ok_and(|d| !d.is_empty())
+        || std::env::var("XDG_SESSION_TYPE").is_ok_and(|t| t == "wayland");
+    wayland.then(|| WAYLAND_UNSUPPORTED.to_string())
+}
+
+fn register(app: &AppHandle, settings: &ShortcutSettings) -> Vec<ShortcutConflict> {
+    let global_shortcut = app.global_shortcut();
+    if let Err(e) = global_shortcut.unregister_all() {
+        eprintln!("Failed to unregister shortcuts: {}", e);
+    }
+
+    settings
+        .bindings()
+        .into_iter()
+        .filter_map(|(action, binding)| {
+            global_shortcut
+                .register(binding)
+                .err()
+                .map(|e| ShortcutConflict {
+                    action,
+                    shortcut: binding.to_string(),
+                    message: e.to_string(),
+                })
+        })
+        .collect()
+}
+
+/// Handler for the global shortcut plugin.
+pub fn on_shortcut(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let Some(action) = app.state::<ShortcutStore>().action_for(shortcut) else {
+        return;
+    };
+
+    let result = match action {
+        Action::ToggleLauncher => toggle_launcher(app),
+        Action::StartDefaultProfile => start_default_profile(app),
+        Action::ToggleWidget => toggle_widget(app),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Shortcut {:?} failed: {}", action, e);
+        let _ = app.emit("shortcut-failed", &e);
+    }
+}
+
+fn toggle_launcher(app: &AppHandle) -> Result<(), String> {
+    let window = app
+        .get_webview_window(WindowKind::Launcher.label())
+        .ok_or("Launcher window not found")?;
+
+    let visible = window.is_visible().unwrap_or(false);
+    let focused = window.is_focused().unwrap_or(false);
+    if visible && focused {
+        window.hide().map_err(|e| e.to_string())
+    } else {
+        window.show().map_err(|e| e.to_string())?;
+        window.set_focus().map_err(|e| e.to_string())
+    }
+}
+
+fn start_default_profile(app: &AppHandle) -> Result<(), String> {
+    if app.state::<SessionState>().current().is_some() {
+        return Err("A session is already running".to_string());
+    }
+
+    let profiles = app.state::<ProfileStore>();
+    let settings = app.state::<ShortcutStore>().status().settings;
+    let profile = settings
+        .default_profile
+        .and_then(|id| profiles.get(&id))
+        .or_else(|| profiles.list().into_iter().next())
+        .ok_or("No profile to start")?;
+
+    commands::start_session(app, SessionRequest::from_profile(profile)).map(|_| ())
+}
+
+fn toggle_widget(app: &AppHandle) -> Result<(), String> {
+    let shown = app
+        .get_webview_window(WindowKind::Widget.label())
+        .is_some_and(|window| window.is_visible().unwrap_or(false));
+    if shown {
+        return windows::hide(app, WindowKind::Widget);
+    }
+
+    if app.state::<SessionState>().current().is_none() {
+        return Err("No active session".to_string());
+    }
+    windows::open(app, WindowKind::Widget).map(|_| ())
+}
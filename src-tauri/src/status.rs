@@ -0,0 +1,146 @@
+use std::fs;
+use std::io::Write;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+
+use crate::app_blocker::AppBlocker;
+use crate::native_messaging::BrowserBridge;
+use crate::pomodoro::{Phase, PomodoroState};
+use crate::session::SessionState;
+use crate::xdg;
+
+const UPDATE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// What status-bar widgets show, written to the status file and streamed
+/// to socket clients as one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+pub struct Status {
+    pub active: bool,
+    pub goal: Option<String>,
+    /// Seconds until the session ends.
+    pub remaining: i64,
+    pub ends_at: Option<i64>,
+    pub paused: bool,
+    pub phase: Option<Phase>,
+    pub blocked_sites: usize,
+    pub blocked_apps: usize,
+    /// Blocked app launches and site visits so far.
+    pub block_attempts: u32,
+}
+
+pub fn status_path() -> PathBuf {
+    xdg::runtime_dir().join("brisk").join("status.json")
+}
+
+pub fn socket_path() -> PathBuf {
+    xdg::runtime_dir().join("brisk").join("status.sock")
+}
+
+fn current_status(app: &AppHandle) -> Status {
+    let Some(session) = app.state::<SessionState>().current() else {
+        return Status::default();
+    };
+
+    let app_attempts: u32 = app
+        .state::<Mutex<AppBlocker>>()
+        .lock()
+        .unwrap()
+        .get_block_attempts()
+        .values()
+        .sum();
+    let site_attempts = app.state::<BrowserBridge>().blocked_visits().len() as u32;
+
+    Status {
+        active: true,
+        remaining: (session.ends_at - Utc::now().timestamp()).max(0),
+        ends_at: Some(session.ends_at),
+        paused: session.pause.is_some(),
+        phase: app.state::<PomodoroState>().current().map(|p| p.phase),
+        blocked_sites: session.blocked_things.len(),
+        blocked_apps: session.blocked_apps.len(),
+        block_attempts: app_attempts + site_attempts,
+        goal: Some(session.goal),
+    }
+}
+
+/// Keeps the status file up to date and streams every change to clients
+/// of the status socket, so widgets need neither poll nor talk to the GUI.
+pub fn start(app: AppHandle) -> Result<(), String> {
+    let path = socket_path();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create status directory: {}", e))?;
+    }
+    // A socket left behind by a crashed instance would make bind fail
+    let _ = fs::remove_file(&path);
+
+    let listener = UnixListener::bind(&path)
+        .map_err(|e| format!("Failed to bind status socket {:?}: {}", path, e))?;
+
+    let clients: Arc<Mutex<Vec<UnixStream>>> = Arc::new(Mutex::new(Vec::new()));
+    let last = Arc::new(Mutex::new(current_status(&app)));
+
+    let accepted = Arc::clone(&clients);
+    let latest = Arc::clone(&last);
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            // A widget that stops reading must not stall updates for others
+            let _ = stream.set_write_timeout(Some(UPDATE_INTERVAL));
+            // New clients get the current status right away instead of
+            // waiting for the next change
+            let status = latest.lock().unwrap().clone();
+            if send(&mut stream, &status).is_ok() {
+                accepted.lock().unwrap().push(stream);
+            }
+        }
+    });
+
+    if let Err(e) = write_status_file(&last.lock().unwrap()) {
+        eprintln!("Failed to write status file: {}", e);
+    }
+
+    thread::spawn(move || loop {
+        thread::sleep(UPDATE_INTERVAL);
+
+        let status = current_status(&app);
+        if *last.lock().unwrap() == status {
+            continue;
+        }
+
+        if let Err(e) = write_status_file(&status) {
+            eprintln!("Failed to write status file: {}", e);
+        }
+        // Drop clients whose widget went away
+        clients
+            .lock()
+            .unwrap()
+            .retain_mut(|stream| send(stream, &status).is_ok());
+
+        *last.lock().unwrap() = status;
+    });
+
+    Ok(())
+}
+
+fn send(stream: &mut UnixStream, status: &Status) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(status)?;
+    line.push('\n');
+    stream.write_all(line.as_bytes())
+}
+
+/// Replaces the file atomically so readers never see a partial write.
+fn write_status_file(status: &Status) -> Result<(), String> {
+    let path = status_path();
+    let temp = path.with_extension("json.tmp");
+
+    let json =
+        serde_json::to_string_pretty(status).map_err(|e| format!("Failed to serialize {}", e))?;
+    fs::write(&temp, json).map_err(|e| format!("Failed to write: {}", e))?;
+    fs::rename(&temp, &path).map_err(|e| format!("Failed to write: {}", e))
+}
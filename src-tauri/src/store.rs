@@ -0,0 +1,101 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Reads the JSON file at `path`, or `None` when there is none yet.
+///
+/// A file that fails to parse is moved aside to `<name>.corrupt` instead of
+/// being overwritten with defaults on the next save, so it can still be
+/// recovered by hand.
+pub fn read<T: DeserializeOwned>(path: &Path) -> Option<T> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == ErrorKind::NotFound => return None,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    match serde_json::from_str(&content) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            let aside = corrupt_path(path);
+            eprintln!(
+                "Failed to parse {}: {}; moving it to {}",
+                path.display(),
+                e,
+                aside.display()
+            );
+            if let Err(e) = fs::rename(path, &aside) {
+                eprintln!("Failed to move {} aside: {}", path.display(), e);
+            }
+            None
+        }
+    }
+}
+
+/// Writes `value` to `path` as pretty-printed JSON, creating its directory.
+pub fn write<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+
+    let json =
+        serde_json::to_string_pretty(value).map_err(|e| format!("Failed to serialize {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write: {}", e))
+}
+
+fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".corrupt");
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_path(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("brisk-store-{}-{}", std::process::id(), test));
+        let _ = fs::remove_dir_all(&dir);
+        dir.join("store.json")
+    }
+
+    #[test]
+    fn round_trips_through_a_new_directory() {
+        let path = store_path("round-trip");
+
+        write(&path, &vec!["a".to_string(), "b".to_string()]).unwrap();
+
+        assert_eq!(
+            read::<Vec<String>>(&path),
+            Some(vec!["a".into(), "b".into()])
+        );
+    }
+
+    #[test]
+    fn missing_file_reads_as_none() {
+        let path = store_path("missing");
+
+        assert_eq!(read::<Vec<String>>(&path), None);
+        assert!(!corrupt_path(&path).exists());
+    }
+
+    #[test]
+    fn unparsable_file_is_moved_aside() {
+        let path = store_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "[\"a\",").unwrap();
+
+        assert_eq!(read::<Vec<String>>(&path), None);
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(corrupt_path(&path)).unwrap(), "[\"a\",");
+
+        write(&path, &Vec::<String>::new()).unwrap();
+        assert_eq!(fs::read_to_string(corrupt_path(&path)).unwrap(), "[\"a\",");
+    }
+}
@@ -0,0 +1,215 @@
+use std::thread;
+use std::time::Duration;
+
+use chrono::Utc;
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem};
+use tauri::{AppHandle, Listener, Manager, Window, WindowEvent, Wry};
+
+use crate::commands::{self, SessionRequest};
+use crate::profiles::{Profile, ProfileStore};
+use crate::session::SessionState;
+use crate::windows::{self, WindowKind};
+
+/// The tray icon declared in `tauri.conf.json`.
+const TRAY_ID: &str = "brisk";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Session events after which the menu is refreshed right away instead of
+/// on the next tick.
+const SESSION_EVENTS: [&str; 5] = [
+    "session-started",
+    "session-ended",
+    "session-paused",
+    "session-resumed",
+    "session-unlocked",
+];
+
+/// Tray menu items whose text or state follows the session.
+struct TrayMenu {
+    remaining: MenuItem<Wry>,
+    start: MenuItem<Wry>,
+    pause: MenuItem<Wry>,
+    stop: MenuItem<Wry>,
+}
+
+pub fn setup(app: &AppHandle) -> tauri::Result<()> {
+    let remaining = MenuItem::with_id(app, "remaining", "No active session", false, None::<&str>)?;
+    let start = MenuItem::with_id(app, "start", "Start last profile", false, None::<&str>)?;
+    let pause = MenuItem::with_id(app, "pause", "Pause", false, None::<&str>)?;
+    let stop = MenuItem::with_id(app, "stop", "Stop session", false, None::<&str>)?;
+    let stats = MenuItem::with_id(app, "stats", "Open stats", true, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show Brisk", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &remaining,
+            &PredefinedMenuItem::separator(app)?,
+            &start,
+            &pause,
+            &stop,
+            &PredefinedMenuItem::separator(app)?,
+            &stats,
+            &show,
+            &quit,
+        ],
+    )?;
+
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        eprintln!("No tray icon with id {} is configured", TRAY_ID);
+        return Ok(());
+    };
+    tray.set_menu(Some(menu))?;
+    tray.on_menu_event(on_menu_event);
+
+    app.manage(TrayMenu {
+        remaining,
+        start,
+        pause,
+        stop,
+    });
+    refresh(app);
+
+    for event in SESSION_EVENTS {
+        let handle = app.clone();
+        app.listen_any(event, move |_| refresh(&handle));
+    }
+
+    // Keeps the remaining time current
+    let app = app.clone();
+    thread::spawn(move || loop {
+        thread::sleep(REFRESH_INTERVAL);
+        refresh_session(&app);
+    });
+
+    Ok(())
+}
+
+/// Hides the launcher instead of closing it while a session runs, so the
+/// session keeps being enforced from the tray. Other windows close as usual.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    if window.label() != WindowKind::Launcher.label() {
+        return;
+    }
+    if let WindowEvent::CloseRequested { api, .. } = event {
+        if window.state::<SessionState>().current().is_some() {
+            api.prevent_close();
+            let _ = window.hide();
+        }
+    }
+}
+
+/// The profile of the most recent session that was started from one.
+fn last_profile(app: &AppHandle) -> Option<Profile> {
+    let id = commands::get_all_sessions()
+        .ok()?
+        .into_iter()
+        .find_map(|session| session.profile)?;
+    app.state::<ProfileStore>().get(&id)
+}
+
+fn format_remaining(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// Updates the whole menu, including the start item, which has to look
+/// through the session history.
+fn refresh(app: &AppHandle) {
+    refresh_session(app);
+
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let running = app.state::<SessionState>().current().is_some();
+    let start = match last_profile(app) {
+        Some(profile) if !running => Some(format!("Start {}", profile.name)),
+        _ => None,
+    };
+    let _ = menu.start.set_enabled(start.is_some());
+    let _ = menu
+        .start
+        .set_text(start.as_deref().unwrap_or("Start last profile"));
+}
+
+/// Updates the remaining time and the pause and stop items.
+fn refresh_session(app: &AppHandle) {
+    let Some(menu) = app.try_state::<TrayMenu>() else {
+        return;
+    };
+    let session = app.state::<SessionState>().current();
+    let now = Utc::now().timestamp();
+
+    let remaining = match &session {
+        Some(session) => match &session.pause {
+            Some(pause) => format!(
+                "Paused, resumes in {}",
+                format_remaining(pause.resumes_at - now)
+            ),
+            None => format!(
+                "{}: {} left",
+                session.goal,
+                format_remaining(session.ends_at - now)
+            ),
+        },
+        None => "No active session".to_string(),
+    };
+    let _ = menu.remaining.set_text(&remaining);
+    if let Some(tray) = app.tray_by_id(TRAY_ID) {
+        let _ = tray.set_tooltip(Some(format!("Brisk: {}", remaining)));
+    }
+
+    let paused = session.as_ref().is_some_and(|s| s.pause.is_some());
+    let _ = menu.pause.set_text(if paused { "Resume" } else { "Pause" });
+    let _ = menu.pause.set_enabled(
+        session
+            .as_ref()
+            .is_some_and(|s| paused || (s.remaining_pause_budget() > 0 && !s.is_locked(now))),
+    );
+    let _ = menu
+        .stop
+        .set_enabled(session.as_ref().is_some_and(|s| !s.is_locked(now)));
+}
+
+fn on_menu_event(app: &AppHandle, event: MenuEvent) {
+    let result = match event.id().as_ref() {
+        "start" => last_profile(app)
+            .ok_or_else(|| "No profile has been used yet".to_string())
+            .and_then(|profile| commands::start_session(app, SessionRequest::from_profile(profile)))
+            .map(|_| ()),
+        "pause" => {
+            let paused = app
+                .state::<SessionState>()
+                .current()
+                .is_some_and(|s| s.pause.is_some());
+            if paused {
+                commands::resume(app).map(|_| ())
+            } else {
+                commands::pause_session(None, app.clone()).map(|_| ())
+            }
+        }
+        "stop" => commands::unblock_all_sites(app.clone()).map(|_| ()),
+        "stats" => windows::open(app, WindowKind::Stats).map(|_| ()),
+        "show" => windows::open(app, WindowKind::Launcher).map(|_| ()),
+        "quit" => {
+            app.exit(0);
+            Ok(())
+        }
+        _ => Ok(()),
+    };
+
+    if let Err(e) = result {
+        eprintln!("Tray action {} failed: {}", event.id().as_ref(), e);
+    }
+    refresh(app);
+}
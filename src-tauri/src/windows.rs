@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{
+    AppHandle, Listener, LogicalSize, Manager, PhysicalPosition, WebviewUrl, WebviewWindow,
+    WebviewWindowBuilder, Window, WindowEvent,
+};
+
+use crate::session::SessionState;
+use crate::store;
+use crate::xdg;
+
+/// The windows the app is made of. The launcher is declared in
+/// `tauri.conf.json`; the others are created on demand.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowKind {
+    Launcher,
+    Widget,
+    Stats,
+}
+
+impl WindowKind {
+    const ALL: [WindowKind; 3] = [WindowKind::Launcher, WindowKind::Widget, WindowKind::Stats];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            WindowKind::Launcher => "main",
+            WindowKind::Widget => "widget",
+            WindowKind::Stats => "stats",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.label() == label)
+    }
+
+    /// The frontend route the window shows.
+    fn route(self) -> &'static str {
+        match self {
+            WindowKind::Launcher => "index.html",
+            WindowKind::Widget => "widget",
+            WindowKind::Stats => "stats",
+        }
+    }
+
+    /// Default inner size in logical pixels.
+    fn size(self) -> (f64, f64) {
+        match self {
+            WindowKind::Launcher => (1000.0, 750.0),
+            WindowKind::Widget => (400.0, 80.0),
+            WindowKind::Stats => (1000.0, 750.0),
+        }
+    }
+
+    fn min_size(self) -> (f64, f64) {
+        match self {
+            WindowKind::Launcher => (900.0, 600.0),
+            WindowKind::Widget => (400.0, 80.0),
+            WindowKind::Stats => (900.0, 600.0),
+        }
+    }
+}
+
+/// Where a window was last placed.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// Outer position in physical pixels, the one coordinate space shared
+    /// by all monitors.
+    pub x: i32,
+    pub y: i32,
+    /// Inner size in logical pixels, so a window keeps its apparent size
+    /// when it comes back on a monitor with a different scale factor.
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Window geometry persisted in the app data directory.
+pub struct WindowStateStore {
+    path: PathBuf,
+    windows: Mutex<HashMap<String, WindowGeometry>>,
+}
+
+impl WindowStateStore {
+    pub fn load() -> Self {
+        Self::load_from(xdg::app_data_dir().join("windows.json"))
+    }
+
+    pub fn load_from(path: PathBuf) -> Self {
+        let windows = store::read(&path).unwrap_or_default();
+
+        Self {
+            path,
+            windows: Mutex::new(windows),
+        }
+    }
+
+    fn get(&self, kind: WindowKind) -> Option<WindowGeometry> {
+        self.windows.lock().unwrap().get(kind.label()).copied()
+    }
+
+    fn record(&self, kind: WindowKind, geometry: WindowGeometry) {
+        self.windows
+            .lock()
+            .unwrap()
+            .insert(kind.label().to_string(), geometry);
+    }
+
+    fn save(&self) -> Result<(), String> {
+        store::write(&self.path, &*self.windows.lock().unwrap())
+    }
+}
+
+/// Puts the launcher where it was left and swaps it for the widget while
+/// a session runs, however the session was started or ended.
+pub fn setup(app: &AppHandle) {
+    if let Some(launcher) = app.get_webview_window(WindowKind::Launcher.label()) {
+        let geometry = app.state::<WindowStateStore>().get(WindowKind::Launcher);
+        if let Some(geometry) = geometry {
+            let (min_width, min_height) = WindowKind::Launcher.min_size();
+            let _ = launcher.set_size(LogicalSize::new(
+                geometry.width.max(min_width),
+                geometry.height.max(min_height),
+            ));
+        }
+        place(&launcher, geometry);
+    }
+
+    let handle = app.clone();
+    app.listen_any("session-started", move |_| {
+        if let Err(e) = show_widget(&handle) {
+            eprintln!("Failed to open the widget: {}", e);
+        }
+    });
+
+    // The widget closes itself once it has played its sound
+    let handle = app.clone();
+    app.listen_any("session-ended", move |_| {
+        if let Err(e) = open(&handle, WindowKind::Launcher) {
+            eprintln!("Failed to show the launcher: {}", e);
+        }
+    });
+
+    if app.state::<SessionState>().current().is_some() {
+        if let Err(e) = show_widget(app) {
+            eprintln!("Failed to open the widget: {}", e);
+        }
+    }
+}
+
+fn show_widget(app: &AppHandle) -> Result<(), String> {
+    open(app, WindowKind::Widget)?;
+    hide(app, WindowKind::Launcher)
+}
+
+/// Shows the window, creating it where it was last left if it is not
+/// open yet.
+pub fn open(app: &AppHandle, kind: WindowKind) -> Result<WebviewWindow, String> {
+    if let Some(window) = app.get_webview_window(kind.label()) {
+        window
+            .show()
+            .map_err(|e| format!("Failed to show window: {}", e))?;
+        let _ = window.set_focus();
+        return Ok(window);
+    }
+
+    let geometry = app.state::<WindowStateStore>().get(kind);
+    let (width, height) = geometry.map(|g| (g.width, g.height)).unwrap_or(kind.size());
+    let (min_width, min_height) = kind.min_size();
+    let widget = kind == WindowKind::Widget;
+
+    let window = WebviewWindowBuilder::new(app, kind.label(), WebviewUrl::App(kind.route().into()))
+        .title("brisk")
+        .inner_size(width.max(min_width), height.max(min_height))
+        .min_inner_size(min_width, min_height)
+        .decorations(false)
+        .transparent(true)
+        .resizable(!widget)
+        .always_on_top(widget)
+        .skip_taskbar(widget)
+        .visible(false)
+        .build()
+        .map_err(|e| format!("Failed to create window: {}", e))?;
+
+    place(&window, geometry);
+    window
+        .show()
+        .map_err(|e| format!("Failed to show window: {}", e))?;
+    let _ = window.set_focus();
+
+    Ok(window)
+}
+
+pub fn hide(app: &AppHandle, kind: WindowKind) -> Result<(), String> {
+    match app.get_webview_window(kind.label()) {
+        Some(window) => window
+            .hide()
+            .map_err(|e| format!("Failed to hide window: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Destroys the window, so it starts fresh the next time it is opened.
+/// Unlike closing, this is not intercepted while a session runs.
+pub fn close(app: &AppHandle, kind: WindowKind) -> Result<(), String> {
+    match app.get_webview_window(kind.label()) {
+        Some(window) => window
+            .destroy()
+            .map_err(|e| format!("Failed to close window: {}", e)),
+        None => Ok(()),
+    }
+}
+
+/// Moves the window to its saved position, or centers it when there is
+/// none or the monitor it was on is gone.
+fn place(window: &WebviewWindow, geometry: Option<WindowGeometry>) {
+    let monitors = window.available_monitors().unwrap_or_default();
+    let on_screen = geometry.filter(|g| {
+        monitors.iter().any(|monitor| {
+            let position = monitor.position();
+            let size = monitor.size();
+            g.x >= position.x
+                && g.y >= position.y
+                && g.x < position.x + size.width as i32
+                && g.y < position.y + size.height as i32
+        })
+    });
+
+    match on_screen {
+        Some(g) => {
+            let _ = window.set_position(PhysicalPosition::new(g.x, g.y));
+        }
+        None => {
+            let _ = window.center();
+        }
+    }
+}
+
+/// Writes out where the windows were left. Quitting from the tray exits
+/// without closing them, so this also runs when the app exits.
+pub fn save_geometry(app: &AppHandle) {
+    if let Err(e) = app.state::<WindowStateStore>().save() {
+        eprintln!("Failed to save window positions: {}", e);
+    }
+}
+
+/// Remembers where windows are moved and resized to, and writes it out
+/// when one goes away.
+pub fn on_window_event(window: &Window, event: &WindowEvent) {
+    let Some(kind) = WindowKind::from_label(window.label()) else {
+        return;
+    };
+    let Some(store) = window.try_state::<WindowStateStore>() else {
+        return;
+    };
+
+    match event {
+        WindowEvent::Moved(_) | WindowEvent::Resized(_) => {
+            // A maximized window's size says nothing about where to put it
+            if window.is_maximized().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+                return;
+            }
+            let (Ok(position), Ok(size), Ok(scale_factor)) = (
+                window.outer_position(),
+                window.inner_size(),
+                window.scale_factor(),
+            ) else {
+                return;
+            };
+            let size = size.to_logical::<f64>(scale_factor);
+            store.record(
+                kind,
+                WindowGeometry {
+                    x: position.x,
+                    y: position.y,
+                    width: size.width,
+                    height: size.height,
+                },
+            );
+        }
+        WindowEvent::CloseRequested { .. } | WindowEvent::Destroyed => {
+            if let Err(e) = store.save() {
+                eprintln!("Failed to save window positions: {}", e);
+            }
+        }
+        _ => {}
+    }
+}
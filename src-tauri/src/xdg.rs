@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+fn home() -> String {
+    std::env::var("HOME").unwrap_or_default()
+}
+
+fn non_empty_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|v| !v.is_empty())
+}
+
+/// `$XDG_DATA_HOME`, defaulting to `~/.local/share`.
+pub fn data_home() -> PathBuf {
+    non_empty_var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}/.local/share", home())))
+}
+
+/// `$XDG_DATA_DIRS`, defaulting to `/usr/local/share:/usr/share`.
+pub fn data_dirs() -> Vec<PathBuf> {
+    non_empty_var("XDG_DATA_DIRS")
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string())
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+/// `$XDG_CONFIG_HOME`, defaulting to `~/.config`.
+pub fn config_home() -> PathBuf {
+    non_empty_var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(format!("{}/.config", home())))
+}
+
+/// The data home followed by the data dirs, each joined with `subdir`.
+pub fn data_paths(subdir: &str) -> Vec<PathBuf> {
+    std::iter::once(data_home())
+        .chain(data_dirs())
+        .map(|dir| dir.join(subdir))
+        .collect()
+}
+
+/// `$XDG_RUNTIME_DIR`, falling back to the temp dir on systems without one.
+pub fn runtime_dir() -> PathBuf {
+    non_empty_var("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+}
+
+/// Where Brisk keeps its own persistent files, under the data home.
+pub fn app_data_dir() -> PathBuf {
+    data_home().join("brisk")
+}
@@ -1,4 +1,6 @@
+import { useEffect } from "react";
 import { Routes, Route, useLocation, Navigate } from "react-router-dom";
+import { listen } from "@tauri-apps/api/event";
 import { AnimatePresence } from "motion/react";
 import "./App.css";
 import { MainPage } from "@/pages/MainPage";
@@ -7,7 +9,7 @@ import { StatsPage } from "@/pages/StatsPage";
 import { OnboardingPage } from "@/pages/OnboardingPage";
 import { AnimatedRoute } from "@/components/AnimatedRoute";
 import { ThemeProvider } from "./components/theme-provider";
-import { Toaster } from "sonner";
+import { Toaster, toast } from "sonner";
 import { useOnboardingStore } from "@/store/onboardingStore";
 
 function ProtectedRoute({ children }: { children: React.ReactNode }) {
@@ -23,6 +25,33 @@ function ProtectedRoute({ children }: { children: React.ReactNode }) {
 export default function App() {
   const location = useLocation();
 
+  // Global shortcuts are registered by the backend, which reports bindings
+  // it could not grab, actions that failed and sessions where shortcuts
+  // cannot work at all
+  useEffect(() => {
+    const unlistenConflicts = listen<{ shortcut: string; message: string }[]>(
+      "shortcut-conflicts",
+      (event) => {
+        for (const conflict of event.payload) {
+          toast.error(`Shortcut ${conflict.shortcut} is unavailable`, {
+            description: conflict.message,
+          });
+        }
+      }
+    );
+    const unlistenFailed = listen<string>("shortcut-failed", (event) => {
+      toast.error(event.payload);
+    });
+    const unlistenUnsupported = listen<string>("shortcuts-unsupported", (event) => {
+      toast.warning(event.payload);
+    });
+    return () => {
+      unlistenConflicts.then((fn) => fn());
+      unlistenFailed.then((fn) => fn());
+      unlistenUnsupported.then((fn) => fn());
+    };
+  }, []);
+
   return (
     <ThemeProvider defaultTheme="dark" storageKey="brisk-ui-theme">
       <AnimatePresence mode="wait">
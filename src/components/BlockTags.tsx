@@ -9,6 +9,26 @@ import { cn } from "@/lib/utils"
 import { searchSites, type Site } from "@/lib/constants"
 import type { Tag, InstalledApp } from "@/lib/types"
 
+/** An app's icon as resolved by the backend, or a generic one. */
+function AppIcon({ url, icon, className }: { url?: string | null, icon?: string, className?: string }) {
+  const [resolved, setResolved] = useState<string | null>(url ?? null)
+
+  useEffect(() => {
+    // Tags from saved profiles only carry the icon name
+    if (url || !icon) {
+      setResolved(url ?? null)
+      return
+    }
+    invoke<string | null>('resolve_icon', { icon })
+      .then(setResolved)
+      .catch(() => setResolved(null))
+  }, [url, icon])
+
+  return resolved
+    ? <img src={resolved} alt="" className={cn("object-contain", className)} />
+    : <AppWindow className={className} />
+}
+
 interface BlockTagsProps {
   tags: Tag[]
   onRemoveTag: (id: string) => void
@@ -77,7 +97,9 @@ export function BlockTags({ tags, onRemoveTag, onAddTag }: BlockTagsProps) {
       label: app.display_name,
       type: 'app',
       executable: app.executable,
-      icon: app.icon || undefined
+      icon: app.icon || undefined,
+      iconUrl: app.icon_url || undefined,
+      source: app.source || undefined
     })
     setAppsInput("")
     setShowSuggestions(false)
@@ -168,7 +190,7 @@ export function BlockTags({ tags, onRemoveTag, onAddTag }: BlockTagsProps) {
               <div className="flex items-center gap-2 flex-wrap flex-1 w-full">
                 {tags.filter(t => t.type === 'app').map(tag => (
                   <Badge key={tag.id} className="bg-gradient-to-br from-red-500/20 to-red-400/10 dark:from-red-500/15 dark:to-red-400/5 border border-red-400/30 dark:border-red-400/20 text-red-900 dark:text-red-200 rounded-full px-3 py-1.5 backdrop-blur-md shadow-[0_4px_16px_0_rgba(239,68,68,0.2)] flex items-center gap-2 h-8 text-xs font-medium">
-                    <AppWindow className="size-3" />
+                    <AppIcon url={tag.iconUrl} icon={tag.icon} className="size-3.5" />
                     <span>{tag.label}</span>
                     <button onClick={() => onRemoveTag(tag.id)} className="ml-1 hover:bg-red-400/20 dark:hover:bg-red-400/30 rounded-full p-0.5 transition-colors">
                       <X className="size-3" />
@@ -195,6 +217,7 @@ export function BlockTags({ tags, onRemoveTag, onAddTag }: BlockTagsProps) {
                 ))
               : (currentSuggestions as InstalledApp[]).map((a, i) => (
                   <button key={`${a.name}-${i}`} onClick={() => handleSelectApp(a)} className={cn("w-full text-left px-4 py-2.5 text-sm text-gray-900 dark:text-white hover:bg-red-500/10 dark:hover:bg-red-500/20 transition-colors flex items-center gap-2", i === selectedIndex && "bg-red-500/15 dark:bg-red-500/25")}>
+                    <AppIcon url={a.icon_url} className="size-5 shrink-0" />
                     <span className="font-medium">{a.display_name}</span>
                     <span className="text-xs text-gray-500 dark:text-white/50">({a.categories.join(', ') || 'Application'})</span>
                   </button>
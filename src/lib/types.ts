@@ -4,6 +4,8 @@ export interface Tag {
   type: "website" | "app";
   executable?: string;
   icon?: string;
+  iconUrl?: string;
+  source?: string;
 }
 
 export interface InstalledApp {
@@ -11,5 +13,58 @@ export interface InstalledApp {
   display_name: string;
   executable: string;
   icon: string | null;
+  icon_url: string | null;
   categories: string[];
+  startup_wm_class: string | null;
+  source: string | null;
+}
+
+export type EnforcementMode = "standard" | "strict";
+
+export interface Profile {
+  id: string;
+  name: string;
+  duration: number;
+  site_rules: string[];
+  app_rules: string[];
+  enforcement: EnforcementMode;
+}
+
+export type Weekday = "Mon" | "Tue" | "Wed" | "Thu" | "Fri" | "Sat" | "Sun";
+
+export interface Schedule {
+  id: string;
+  name: string;
+  profile: string;
+  days: Weekday[];
+  start: string;
+  end: string;
+  enabled: boolean;
+  last_triggered: number | null;
+}
+
+export interface ActiveSession {
+  goal: string;
+  started_at: number;
+  ends_at: number;
+  profile: string | null;
+  enforcement: EnforcementMode;
+  schedule: string | null;
+}
+
+export type Resolver =
+  | "systemd_resolved"
+  | "nscd"
+  | "dnsmasq"
+  | "network_manager_dnsmasq";
+
+export interface FlushOutcome {
+  resolver: Resolver;
+  flushed: boolean;
+  command: string | null;
+  error: string | null;
+}
+
+export interface FlushReport {
+  outcomes: FlushOutcome[];
 }
@@ -1,5 +1,4 @@
 import { useState } from "react";
-import { useNavigate } from "react-router-dom";
 import { invoke } from "@tauri-apps/api/core";
 import { GoalInput } from "@/components/GoalInput";
 import { DurationSelect } from "@/components/DurationSelect";
@@ -7,19 +6,15 @@ import { BlockTags } from "@/components/BlockTags";
 import { FooterButtons } from "@/components/FooterButtons";
 import { Layout } from "@/components/Layout";
 import { ModeToggle } from "@/components/mode-toggle";
-import { useSessionStore } from "@/store/sessionStore";
 import { parseDuration } from "@/lib/utils";
 import { Tag } from "@/lib/types";
 
 export function MainPage() {
-  const navigate = useNavigate();
   const [goal, setGoal] = useState("");
   const [duration, setDuration] = useState("1 hour");
   const [tags, setTags] = useState<Tag[]>([]);
   const [isLoading, setIsLoading] = useState(false);
 
-  const { setSession } = useSessionStore();
-
   const handleRemoveTag = (id: string) => {
     setTags(tags.filter((tag) => tag.id !== id));
   };
@@ -53,7 +48,7 @@ export function MainPage() {
 
       const blockedApps = tags
         .filter((tag) => tag.type === "app")
-        .map((tag) => `${tag.label}|||${tag.executable || tag.label}|||${tag.icon || ''}`);
+        .map((tag) => `${tag.label}|||${tag.executable || tag.label}|||${tag.icon || ''}|||${tag.source || ''}`);
 
       const result = await invoke<string>("create_and_store_session", {
         goal: goal.trim(),
@@ -63,8 +58,11 @@ export function MainPage() {
       });
 
       console.log("Focus session started successfully!", result);
-      setSession(goal.trim(), durationSeconds, blockedApps);
-      navigate("/widget");
+      // The backend swaps this window for the widget; the form starts
+      // empty when the launcher comes back
+      setGoal("");
+      setTags([]);
+      setIsLoading(false);
     } catch (error) {
       console.error("Failed to start focus session:", error);
       alert(`Failed to start focus session: ${error}`);
@@ -74,10 +72,9 @@ export function MainPage() {
 
   const handleViewStats = async () => {
     try {
-      navigate("/stats");
-      await invoke<string>("resize_window_to_stats");
+      await invoke<string>("show_window", { window: "stats" });
     } catch (error) {
-      console.error("Failed to resize window to stats:", error);
+      console.error("Failed to open stats:", error);
     }
   };
 
@@ -1,7 +1,5 @@
 import { useEffect, useState } from "react";
-import { useNavigate } from "react-router-dom";
 import { invoke } from "@tauri-apps/api/core";
-import { useSessionStore } from "@/store/sessionStore";
 import { ArrowLeft } from "lucide-react";
 import { Button } from "@/components/ui/button";
 
@@ -51,16 +49,10 @@ function formatDate(timestamp: number): string {
 }
 
 export function StatsPage() {
-  const navigate = useNavigate();
-  const { reset } = useSessionStore();
   const [sessions, setSessions] = useState<Session[]>([]);
   const [loading, setLoading] = useState(true);
 
   useEffect(() => {
-    invoke<string>("resize_window_to_stats").catch((error) => {
-      console.error("Failed to resize window on stats page mount:", error);
-    });
-
     invoke<Session[]>("get_all_sessions")
       .then((data) => {
         setSessions(data);
@@ -73,12 +65,11 @@ export function StatsPage() {
   }, []);
 
   const handleStartNew = async () => {
-    reset();
-    navigate("/");
     try {
-      await invoke<string>("resize_window_to_main");
+      await invoke<string>("show_window", { window: "launcher" });
+      await invoke<string>("close_window", { window: "stats" });
     } catch (error) {
-      console.error("Failed to resize to main:", error);
+      console.error("Failed to return to the launcher:", error);
     }
   };
 
@@ -1,15 +1,20 @@
 import { useEffect, useRef } from "react";
-import { useNavigate } from "react-router-dom";
 import { invoke } from "@tauri-apps/api/core";
+import { listen } from "@tauri-apps/api/event";
 import { useSessionStore } from "@/store/sessionStore";
 import { FocusWidget } from "@/components/FocusWidget";
 import { BlockNotificationWidget } from "@/components/BlockNotificationWidget";
 
+interface ActiveSession {
+  goal: string;
+  started_at: number;
+  ends_at: number;
+  blocked_apps: string[];
+}
+
 export function WidgetPage() {
-  const navigate = useNavigate();
-  const { goal, duration, timeLeft, updateTimeLeft, blockedApps } = useSessionStore();
+  const { goal, duration, timeLeft, setSession, updateTimeLeft, blockedApps } = useSessionStore();
   const timerIntervalRef = useRef<NodeJS.Timeout | null>(null);
-  const isEndingRef = useRef(false);
   
   const hasBlockedApps = blockedApps && blockedApps.length > 0;
 
@@ -36,55 +41,58 @@ export function WidgetPage() {
     });
   };
 
+  // The widget has a window of its own, so it picks the session up from
+  // the backend rather than from the launcher
   useEffect(() => {
-    invoke<string>("resize_window_to_widget").catch((error) => {
-      console.error("Failed to resize window:", error);
-    });
-  }, []);
+    invoke<ActiveSession | null>("get_active_session")
+      .then((session) => {
+        if (!session) {
+          return;
+        }
+        setSession(session.goal, session.ends_at - session.started_at, session.blocked_apps);
+        updateTimeLeft(Math.max(0, session.ends_at - Math.floor(Date.now() / 1000)));
+      })
+      .catch((error) => {
+        console.error("Failed to load the active session:", error);
+      });
+  }, [setSession, updateTimeLeft]);
 
+  // The backend ends the session, at its deadline or when it is stopped
+  // early, and the widget only reacts
   useEffect(() => {
-    if (duration > 0 && timeLeft === 0 && !isEndingRef.current) {
-      isEndingRef.current = true;
+    const unlisten = listen<ActiveSession>("session-ended", (event) => {
+      const closeWidget = () => {
+        invoke<string>("close_window", { window: "widget" }).catch((error) => {
+          console.error("Failed to close the widget:", error);
+        });
+      };
 
-      console.log("Session ended, navigating to stats...");
+      if (event.payload.ends_at > Math.floor(Date.now() / 1000)) {
+        closeWidget();
+        return;
+      }
 
       if (timerIntervalRef.current) {
         clearInterval(timerIntervalRef.current);
         timerIntervalRef.current = null;
       }
+      updateTimeLeft(0);
 
-      const handleSessionEnd = async () => {
-        try {
-          playFinishSound().catch(err => console.error("Sound error:", err));
-          
-          // Calculate duration in minutes
-          const durationMinutes = Math.floor(duration / 60);
-          
-          // Show session complete notification
-          await invoke<string>("show_session_complete_notification", { 
-            durationMinutes: durationMinutes 
-          }).catch(err => console.error("Notification error:", err));
-          
-          navigate("/stats", { replace: true });
-          
-          await invoke<string>("resize_window_to_stats");
-          
-          await invoke<string>("unblock_all_sites");
-        } catch (error) {
-          console.error("Error in session end sequence:", error);
-          navigate("/stats", { replace: true });
-        }
-      };
-
-      handleSessionEnd();
-    }
-  }, [timeLeft, duration, navigate]);
+      invoke<string>("show_window", { window: "stats" }).catch((error) => {
+        console.error("Failed to open stats:", error);
+      });
+      // The widget goes away once the sound has played
+      playFinishSound()
+        .catch(err => console.error("Sound error:", err))
+        .finally(closeWidget);
+    });
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, [updateTimeLeft]);
 
   useEffect(() => {
     if (duration > 0 && timeLeft > 0) {
-      isEndingRef.current = false;
-    }
-    if (duration > 0 && timeLeft > 0 && !isEndingRef.current) {
       if (timerIntervalRef.current) {
         clearInterval(timerIntervalRef.current);
       }